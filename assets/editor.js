@@ -0,0 +1,275 @@
+/* =============================
+   Connexion à editor_server
+   ============================= */
+const AUTOSAVE_DELAY = 1000;
+const RECONNECT_DELAY = 2000;
+
+async function api(method, path, body) {
+  const res = await fetch(path, {
+    method,
+    headers: body ? { 'Content-Type': 'application/json' } : {},
+    body: body ? JSON.stringify(body) : undefined,
+    keepalive: method === 'PUT',
+  });
+  if (res.status === 401) {
+    // session expirée : retour à la page de connexion
+    location.href = '/login';
+    throw new Error('non connecté');
+  }
+  const data = await res.json();
+  if (!res.ok) throw new Error(data.error || res.statusText);
+  return data;
+}
+
+function notePath(path) {
+  return '/api/notes/' + path.split('/').map(encodeURIComponent).join('/');
+}
+
+document.addEventListener('DOMContentLoaded', () => {
+  // 1. Éditeur riche (rich.js) ; « # », « > », « - »… en début de ligne
+  //    deviennent le bloc correspondant
+  const editor = document.getElementById('editor');
+  const toolbar = document.getElementById('toolbar');
+  const rich = new RichEditor(editor, toolbar);
+
+  // 2. Note courante et enregistrement automatique
+  const sidebar = document.getElementById('sidebar');
+  const source = document.getElementById('source');
+  const status = document.getElementById('status');
+  let current = null; // { path, rich, version, text }
+  let dirty = false;
+  let timer = null;
+  let readOnly = false;
+  // édition collaborative : le serveur fusionne les éditions envoyées sur la
+  // WebSocket ; sans elle, on enregistre par PUT
+  let collabPort = null;
+  let socket = null;
+  let sending = null; // texte envoyé, en attente d'accusé de réception
+  let editors = 1;
+
+  function setStatus(text) {
+    const others = editors > 1 ? ` (${editors} éditeurs)` : '';
+    status.textContent = text + others;
+  }
+
+  function markdown() {
+    return current.rich ? rich.markdown() : source.value;
+  }
+
+  async function save() {
+    clearTimeout(timer);
+    if (!current || !dirty) return;
+    if (socket && socket.readyState === WebSocket.OPEN && current.version !== null) {
+      // une seule édition en vol : la suivante part à l'accusé de réception
+      if (sending !== null) return;
+      dirty = false;
+      sending = markdown();
+      socket.send(JSON.stringify({ type: 'edit', base: current.version, text: sending }));
+      setStatus('Enregistrement…');
+      return;
+    }
+    dirty = false;
+    setStatus('Enregistrement…');
+    try {
+      await api('PUT', notePath(current.path), { body: markdown() });
+      setStatus(dirty ? 'Modifié' : 'Enregistré');
+    } catch (e) {
+      dirty = true;
+      setStatus('Erreur : ' + e.message);
+    }
+  }
+
+  function changed() {
+    if (!current) return;
+    dirty = true;
+    setStatus('Modifié');
+    clearTimeout(timer);
+    timer = setTimeout(save, AUTOSAVE_DELAY);
+  }
+
+  rich.onChange = changed;
+  source.addEventListener('input', changed);
+  window.addEventListener('beforeunload', save);
+
+  function show(editable, html, body) {
+    current.rich = editable;
+    if (editable) {
+      rich.setHTML(html);
+      rich.enable(!readOnly);
+    } else {
+      toolbar.classList.add('hidden');
+      source.value = body;
+      source.readOnly = readOnly;
+    }
+    editor.classList.toggle('hidden', !editable);
+    source.classList.toggle('hidden', editable);
+    showMode();
+  }
+
+  function showMode() {
+    if (readOnly) setStatus('Lecture seule');
+    else setStatus(current.rich ? '' : 'Source Markdown (tableaux, HTML… non pris en charge par l’éditeur)');
+  }
+
+  async function open(path) {
+    await save();
+    try {
+      const note = await api('GET', notePath(path));
+      current = { path: note.path, rich: false, version: null, text: note.body };
+      dirty = false;
+      sending = null;
+      editors = 1;
+      show(note.rich, note.html, note.body);
+      if (socket && socket.readyState === WebSocket.OPEN) {
+        socket.send(JSON.stringify({ type: 'open', path: note.path }));
+      }
+      document.getElementById('note-path').textContent = note.path;
+      document.title = note.title;
+      location.hash = encodeURIComponent(note.path);
+      highlightCurrent();
+    } catch (e) {
+      setStatus('Impossible d’ouvrir ' + path + ' : ' + e.message);
+    }
+  }
+
+  function highlightCurrent() {
+    for (const link of sidebar.querySelectorAll('a')) {
+      link.classList.toggle('current', current !== null && link.dataset.path === current.path);
+    }
+  }
+
+  async function createNote(folder) {
+    const title = prompt('Titre de la nouvelle note');
+    if (!title) return;
+    try {
+      const note = await api('POST', '/api/notes', { folder, title, body: '' });
+      await loadNotes();
+      await open(note.path);
+    } catch (e) {
+      setStatus('Création impossible : ' + e.message);
+    }
+  }
+
+  // 3. Liste des notes, lue dans le même dossier que NotesApp
+  async function loadNotes() {
+    const folders = await api('GET', '/api/folders');
+    sidebar.replaceChildren();
+    for (const folder of folders) {
+      const heading = document.createElement('h2');
+      heading.style.color = folder.color;
+      heading.textContent = folder.name;
+      const add = document.createElement('button');
+      add.textContent = '+';
+      add.title = 'Nouvelle note';
+      add.onclick = () => createNote(folder.path);
+      if (!readOnly) heading.append(add);
+      sidebar.append(heading);
+      for (const note of folder.notes) {
+        const link = document.createElement('a');
+        link.href = '#' + encodeURIComponent(note.path);
+        link.dataset.path = note.path;
+        link.textContent = note.title;
+        link.onclick = (e) => {
+          e.preventDefault();
+          open(note.path);
+        };
+        sidebar.append(link);
+      }
+    }
+    highlightCurrent();
+  }
+
+  // 4. Édition collaborative
+  function connect() {
+    const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+    socket = new WebSocket(`${scheme}://${location.hostname}:${collabPort}/`);
+    socket.onopen = () => {
+      if (current) socket.send(JSON.stringify({ type: 'open', path: current.path }));
+    };
+    socket.onmessage = (event) => received(JSON.parse(event.data));
+    socket.onclose = () => {
+      // les éditions non confirmées repartent par PUT
+      socket = null;
+      if (sending !== null) dirty = true;
+      sending = null;
+      if (current) current.version = null;
+      save();
+      setTimeout(connect, RECONNECT_DELAY);
+    };
+  }
+
+  function received(msg) {
+    if (msg.type === 'error') {
+      setStatus('Erreur : ' + msg.error);
+      return;
+    }
+    if (!current || msg.path !== current.path) return;
+    if (msg.editors !== undefined) editors = msg.editors;
+    if (msg.type === 'editors') {
+      if (!dirty && sending === null) showMode();
+    } else if (msg.type === 'ack') {
+      current.version = msg.version;
+      current.text = sending;
+      sending = null;
+      if (dirty) save();
+      else setStatus('Enregistré');
+    } else if (msg.type === 'sync') {
+      if (dirty || sending !== null) {
+        // première synchronisation pendant la frappe : même texte que le GET
+        if (current.version === null && msg.text === current.text) {
+          current.version = msg.version;
+          save();
+        }
+        return;
+      }
+      current.version = msg.version;
+      if (msg.text === current.text) return;
+      current.text = msg.text;
+      if (current.rich && msg.rich) {
+        const before = rich.text();
+        const at = rich.selection();
+        rich.setHTML(msg.html);
+        if (at !== null) rich.select(keepPosition(before, rich.text(), at));
+      } else if (current.rich) {
+        show(false, null, msg.text);
+      } else {
+        const { selectionStart, selectionEnd, value } = source;
+        source.value = msg.text;
+        if (document.activeElement === source) {
+          source.setSelectionRange(
+            keepPosition(value, msg.text, selectionStart),
+            keepPosition(value, msg.text, selectionEnd)
+          );
+        }
+      }
+      showMode();
+    }
+  }
+
+  // Position du curseur après une modification distante : décalée si la
+  // modification est avant lui
+  function keepPosition(before, after, index) {
+    let prefix = 0;
+    const max = Math.min(before.length, after.length);
+    while (prefix < max && before[prefix] === after[prefix]) prefix++;
+    return index <= prefix ? index : Math.max(prefix, index + after.length - before.length);
+  }
+
+  window.addEventListener('hashchange', () => {
+    const path = decodeURIComponent(location.hash.slice(1));
+    if (path && (!current || path !== current.path)) open(path);
+  });
+
+  api('GET', '/api/session')
+    .then((session) => {
+      readOnly = session.read_only;
+      collabPort = session.collab_port;
+      if (window.WebSocket && collabPort) connect();
+      return loadNotes();
+    })
+    .then(() => {
+      const path = decodeURIComponent(location.hash.slice(1));
+      if (path) open(path);
+    })
+    .catch((e) => setStatus('Serveur injoignable : ' + e.message));
+});
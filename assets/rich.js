@@ -0,0 +1,297 @@
+/*
+ * Éditeur riche de editor_server : une zone contenteditable chargée avec le
+ * HTML de la note (rendu par le serveur) et resérialisée en Markdown à
+ * l'enregistrement, pour que le fichier sur disque reste lisible par
+ * l'application de bureau. Le serveur n'y ouvre que les notes qu'il sait
+ * représenter sans perte (voir `rich_editable` dans api.rs).
+ */
+
+/* =============================
+   DOM → Markdown
+   ============================= */
+const BLOCKS = ['P', 'DIV', 'H1', 'H2', 'H3', 'H4', 'H5', 'H6', 'PRE', 'BLOCKQUOTE', 'UL', 'OL', 'LI', 'HR'];
+
+function isBlock(node) {
+  return node.nodeType === 1 && BLOCKS.includes(node.nodeName);
+}
+
+function domToMarkdown(root) {
+  return blocksMarkdown(root).join('\n\n') + '\n';
+}
+
+// Blocs Markdown des enfants de `parent` ; le texte hors bloc (laissé par le
+// navigateur après une frappe) forme un paragraphe
+function blocksMarkdown(parent) {
+  const blocks = [];
+  let run = [];
+  let previous = null; // balise du dernier bloc
+  const flush = () => {
+    const text = paragraph(run);
+    if (text) {
+      blocks.push(text);
+      previous = null;
+    }
+    run = [];
+  };
+  for (const node of parent.childNodes) {
+    if (!isBlock(node)) {
+      run.push(node);
+      continue;
+    }
+    flush();
+    const block = blockMarkdown(node);
+    if (!block) continue;
+    // un navigateur coupe un bloc de code ou une citation à chaque Entrée
+    const last = blocks.length - 1;
+    if (previous === node.nodeName && previous === 'PRE') {
+      blocks[last] = joinCode(blocks[last], block);
+    } else if (previous === node.nodeName && previous === 'BLOCKQUOTE') {
+      blocks[last] += '\n>\n' + block;
+    } else {
+      blocks.push(block);
+    }
+    previous = node.nodeName;
+  }
+  flush();
+  return blocks;
+}
+
+function blockMarkdown(node) {
+  switch (node.nodeName) {
+    case 'H1':
+    case 'H2':
+    case 'H3':
+    case 'H4':
+    case 'H5':
+    case 'H6': {
+      const text = inlineMarkdown(node.childNodes).trim();
+      return text && '#'.repeat(Number(node.nodeName[1])) + ' ' + text;
+    }
+    case 'PRE':
+      return fence(codeText(node).replace(/\n$/, ''));
+    case 'BLOCKQUOTE':
+      return blocksMarkdown(node)
+        .join('\n\n')
+        .split('\n')
+        .map((l) => (l ? '> ' + l : '>'))
+        .join('\n');
+    case 'UL':
+    case 'OL': {
+      let number = Number(node.getAttribute('start') || 1);
+      const items = [];
+      for (const item of node.children) {
+        if (item.nodeName !== 'LI') continue;
+        const marker = node.nodeName === 'OL' ? number++ + '. ' : '- ';
+        const lines = blocksMarkdown(item).join('\n').split('\n');
+        const indent = ' '.repeat(marker.length);
+        items.push(marker + lines.map((l, i) => (i && l ? indent + l : l)).join('\n'));
+      }
+      return items.join('\n');
+    }
+    case 'HR':
+      return '---';
+    default:
+      return [...node.childNodes].some(isBlock) ? blocksMarkdown(node).join('\n\n') : paragraph(node.childNodes);
+  }
+}
+
+// Un paragraphe, dont le début ne doit pas se relire comme un autre bloc
+function paragraph(nodes) {
+  return inlineMarkdown(nodes)
+    .trim()
+    .replace(/^(#{1,6}\s|[-+>]\s)/, '\\$1')
+    .replace(/^(\d+)([.)]\s)/, '$1\\$2');
+}
+
+function fence(body) {
+  const longest = Math.max(2, ...(body.match(/`+/g) || []).map((m) => m.length));
+  const marks = '`'.repeat(longest + 1);
+  return marks + '\n' + body + '\n' + marks;
+}
+
+function joinCode(previous, next) {
+  const body = (block) => block.slice(block.indexOf('\n') + 1, block.lastIndexOf('\n'));
+  return fence(body(previous) + '\n' + body(next));
+}
+
+// Texte d'un bloc de code, dont les lignes sont parfois des <br> ou des <div>
+function codeText(node) {
+  let text = '';
+  for (const child of node.childNodes) {
+    if (child.nodeType === 3) text += child.data;
+    else if (child.nodeName === 'BR') text += '\n';
+    else if (child.nodeName === 'DIV' || child.nodeName === 'P') {
+      if (text && !text.endsWith('\n')) text += '\n';
+      text += codeText(child) + '\n';
+    } else if (child.nodeType === 1) text += codeText(child);
+  }
+  return text;
+}
+
+function inlineMarkdown(nodes) {
+  nodes = [...nodes];
+  // le <br> qui garde ouverte une ligne vide n'est pas un saut de ligne
+  while (nodes.length && nodes[nodes.length - 1].nodeName === 'BR') nodes.pop();
+  return nodes.map(inlineNode).join('');
+}
+
+function inlineNode(node) {
+  if (node.nodeType === 3) {
+    return node.data
+      .replace(/[ \t\r\n]+/g, ' ')
+      .replace(/([\\`*[\]<~])/g, '\\$1')
+      .replace(/(^|[^\p{L}\p{N}])_|_(?![\p{L}\p{N}])/gu, (m) => m.replace('_', '\\_'));
+  }
+  if (node.nodeType !== 1) return '';
+  switch (node.nodeName) {
+    case 'BR':
+      return '\\\n';
+    case 'IMG':
+      return `![${node.getAttribute('alt') || ''}](${link(node.getAttribute('src') || '')})`;
+    case 'CODE': {
+      const code = node.textContent;
+      const tick = code.includes('`') ? '`` ' : '`';
+      return tick + code + tick.split('').reverse().join('');
+    }
+    case 'STRONG':
+    case 'B':
+      return wrap('**', inlineMarkdown(node.childNodes));
+    case 'EM':
+    case 'I':
+      return wrap('*', inlineMarkdown(node.childNodes));
+    case 'S':
+    case 'DEL':
+    case 'STRIKE':
+      return wrap('~~', inlineMarkdown(node.childNodes));
+    case 'A':
+      return `[${inlineMarkdown(node.childNodes)}](${link(node.getAttribute('href') || '')})`;
+    default:
+      return inlineMarkdown(node.childNodes);
+  }
+}
+
+// les marqueurs entourent le texte, pas les espaces autour
+function wrap(marker, md) {
+  const [, lead, core, trail] = md.match(/^(\s*)([\s\S]*?)(\s*)$/);
+  return core ? lead + marker + core + marker + trail : md;
+}
+
+function link(url) {
+  return url.replace(/[ ()]/g, encodeURIComponent);
+}
+
+/* =============================
+   Zone d'édition
+   ============================= */
+// Raccourcis Markdown en début de bloc, appliqués à la frappe de l'espace
+const SHORTCUTS = [
+  [/^(#{1,6})$/, (m) => ['formatBlock', 'h' + m[1].length]],
+  [/^>$/, () => ['formatBlock', 'blockquote']],
+  [/^```$/, () => ['formatBlock', 'pre']],
+  [/^[-*+]$/, () => ['insertUnorderedList']],
+  [/^1[.)]$/, () => ['insertOrderedList']],
+];
+
+class RichEditor {
+  constructor(root, toolbar) {
+    this.root = root;
+    this.toolbar = toolbar;
+    this.onChange = () => {};
+    document.execCommand('defaultParagraphSeparator', false, 'p');
+
+    root.addEventListener('input', (e) => {
+      if (e.inputType === 'insertText' && e.data === ' ') this.shortcut();
+      this.onChange();
+    });
+    // le collage n'apporte que du texte : pas de tableau ni de style étranger
+    root.addEventListener('paste', (e) => {
+      e.preventDefault();
+      document.execCommand('insertText', false, e.clipboardData.getData('text/plain'));
+    });
+    // les boutons ne prennent pas le focus, la sélection reste dans la note
+    toolbar.addEventListener('mousedown', (e) => e.preventDefault());
+    toolbar.addEventListener('click', (e) => {
+      const button = e.target.closest('button');
+      if (button) this.command(button.dataset.command, button.dataset.value);
+    });
+  }
+
+  setHTML(html) {
+    this.root.innerHTML = html;
+  }
+
+  markdown() {
+    return domToMarkdown(this.root);
+  }
+
+  enable(enabled) {
+    this.root.contentEditable = enabled ? 'true' : 'false';
+    this.toolbar.classList.toggle('hidden', !enabled);
+  }
+
+  text() {
+    return this.root.textContent;
+  }
+
+  // Position du curseur, en caractères du texte de la note
+  selection() {
+    const sel = getSelection();
+    if (!sel.rangeCount || !this.root.contains(sel.anchorNode)) return null;
+    const range = document.createRange();
+    range.setStart(this.root, 0);
+    range.setEnd(sel.anchorNode, sel.anchorOffset);
+    return range.toString().length;
+  }
+
+  select(index) {
+    const walker = document.createTreeWalker(this.root, NodeFilter.SHOW_TEXT);
+    let node;
+    while ((node = walker.nextNode())) {
+      if (index <= node.length) {
+        getSelection().collapse(node, index);
+        return;
+      }
+      index -= node.length;
+    }
+  }
+
+  command(name, value) {
+    if (name === 'code') {
+      const text = getSelection().toString();
+      if (!text) return;
+      const code = document.createElement('code');
+      code.textContent = text;
+      document.execCommand('insertHTML', false, code.outerHTML);
+    } else if (name === 'createLink' || name === 'insertImage') {
+      const url = prompt(name === 'createLink' ? 'Adresse du lien' : 'Adresse de l’image');
+      if (url) document.execCommand(name, false, url);
+    } else if (name === 'removeFormat') {
+      document.execCommand('removeFormat');
+      document.execCommand('formatBlock', false, 'p');
+    } else {
+      document.execCommand(name, false, value);
+    }
+    this.onChange();
+  }
+
+  // « # », « > », « - »… suivis d'une espace en début de bloc deviennent le bloc
+  shortcut() {
+    const sel = getSelection();
+    let block = sel.anchorNode;
+    while (block && block.parentNode !== this.root && block.nodeName !== 'LI') block = block.parentNode;
+    if (!block || block.nodeName === 'LI' || block.nodeName === 'PRE') return;
+    const range = document.createRange();
+    range.setStart(block, 0);
+    range.setEnd(sel.anchorNode, sel.anchorOffset);
+    const typed = range.toString();
+    for (const [pattern, command] of SHORTCUTS) {
+      const m = typed.slice(0, -1).match(pattern);
+      if (!m) continue;
+      sel.extend(block, 0);
+      document.execCommand('delete');
+      const [name, value] = command(m);
+      document.execCommand(name, false, value);
+      return;
+    }
+  }
+}
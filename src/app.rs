@@ -1,130 +1,170 @@
-use crate::models::{Folder, Note};
+use crate::keymap::{Action, Keymap};
+use crate::nav::{NavHistory, Place};
+use crate::tabs::Workspace;
+use crate::ui::calendar::Calendar;
+use crate::ui::highlight::markdown_job;
+use crate::ui::history::{HistoryPanel, HistoryView};
+use crate::ui::icons::Icons;
+use crate::ui::notes_panel::NotesPanel;
+use crate::ui::palette::{CommandPalette, PaletteEntry};
+use crate::ui::preview::{self, Preview, PreviewMode, ScrollSync};
+use crate::ui::settings::SettingsPanel;
 use crate::ui::sidebar::SideBar;
+use crate::ui::tabs::TabBar;
+use crate::ui::unlock::UnlockPanel;
+use chrono::NaiveDate;
 use eframe::egui;
-use egui::{
-    text::{LayoutJob, TextFormat},
-    Color32, TextEdit, TextStyle, TextureHandle,
-};
-use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use egui::{Key as KeyCode, KeyboardShortcut, Modifiers, PointerButton, TextEdit};
+use egui_commonmark::CommonMarkCache;
 use egui_file_dialog::FileDialog;
-use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use pulldown_cmark::{html, Event, Parser, Tag};
+use notes_app::crypt::{self, Key};
+use notes_app::export;
+use notes_app::fuzzy;
+use notes_app::git::{Repo, Status};
+use notes_app::history::{self, History};
+use notes_app::journal;
+use notes_app::models::{Color, Folder, Note};
+use notes_app::pdf;
+use notes_app::settings::Settings;
+use notes_app::templates::{self, Vars};
+use notes_app::undo::{UndoLog, UndoStack};
+use notes_app::vault::{self, Vault};
+use notes_app::watch::FileWatcher;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, Receiver};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
+use zeroize::Zeroize;
 
 #[derive(Clone)]
 pub enum Msg {
     SelectFolder(usize),
+    SelectNote(usize),
     SelectHome,
     GoBack,
+    GoForward,
     OpenSettings,
-    CreateItem, // bouton +
+    CreateItem,                 // bouton +
+    CreateNote(Option<String>), // modèle choisi, None pour une note vide
+    ExportNote,
+    ExportNotePdf,
+    ExportFolder,
+    ChooseVault,
+    SaveSettings,
+    OpenDay(NaiveDate), // note du jour, créée au besoin
+    PreviousDay,
+    NextDay,
+    ShowMonth(NaiveDate),
+    OpenHistory,
+    SelectVersion(usize),
+    RestoreVersion,
+    CloseHistory,
+    ShowGitHistory(bool),
+    GitCommit,
+    GitPush,
+    GitRefresh,
+    Unlock,
+    CancelUnlock,
+    Lock,
+    Encrypt(bool),       // note ouverte
+    EncryptFolder(bool), // dossier ouvert, notes existantes comprises
+    Undo,
+    Redo,
+    OpenPalette(PaletteMode),
+    ClosePalette,
+    OpenNote(PathBuf),
+    OpenLink(String),        // cible d'un lien [[…]]
+    SelectTab(usize, usize), // volet, onglet
+    CloseTab(usize, usize),
+    MoveTab(usize, usize, usize), // volet, de, vers
+    FocusPane(usize),
+    ToggleSplit,
+    SetPreviewMode(PreviewMode),
 }
 
-fn markdown_job(text: &str, style: &egui::Style) -> LayoutJob {
-    let parser = Parser::new(text);
-    let mut job = LayoutJob::default();
-    let mut fmt = TextFormat {
-        font_id: TextStyle::Body.resolve(style),
-        color: style.visuals.text_color(),
-        ..Default::default()
-    };
-    let mut stack: Vec<TextFormat> = Vec::new();
-    let mut bullet_depth = 0usize;
+/// What the command palette lists.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PaletteMode {
+    /// Every action, then every note.
+    Commands,
+    Notes,
+    /// Notes containing the query.
+    Search,
+}
 
-    for ev in parser {
-        match ev {
-            Event::Start(Tag::Heading(level, _, _)) => {
-                stack.push(fmt.clone());
-                let mut id = TextStyle::Heading.resolve(style);
-                let lvl: usize = level as usize;
-                id.size = id.size - ((lvl.saturating_sub(1)) as f32) * 2.0;
-                fmt.font_id = id;
-            }
-            Event::End(Tag::Heading(..)) => {
-                fmt = stack.pop().unwrap_or(fmt);
-            }
-            Event::Start(Tag::Emphasis) => {
-                stack.push(fmt.clone());
-                fmt.italics = true;
-            }
-            Event::End(Tag::Emphasis) => {
-                fmt = stack.pop().unwrap_or(fmt);
-            }
-            Event::Start(Tag::Strong) => {
-                stack.push(fmt.clone());
-                fmt.color = style.visuals.strong_text_color();
-            }
-            Event::End(Tag::Strong) => {
-                fmt = stack.pop().unwrap_or(fmt);
-            }
-            Event::Start(Tag::List(_)) => {
-                bullet_depth += 1;
-            }
-            Event::End(Tag::List(_)) => {
-                if bullet_depth > 0 {
-                    bullet_depth -= 1;
-                }
-                job.append("\n", 0.0, fmt.clone());
-            }
-            Event::Start(Tag::Item) => {
-                for _ in 0..bullet_depth.saturating_sub(1) {
-                    job.append("  ", 0.0, fmt.clone());
-                }
-                job.append("• ", 0.0, fmt.clone());
-            }
-            Event::End(Tag::Item) => {
-                job.append("\n", 0.0, fmt.clone());
-            }
-            Event::Text(t) => job.append(&t, 0.0, fmt.clone()),
-            Event::Code(t) => {
-                let mut code_fmt = fmt.clone();
-                code_fmt.font_id = TextStyle::Monospace.resolve(style);
-                job.append(&t, 0.0, code_fmt);
-            }
-            Event::SoftBreak | Event::HardBreak => job.append("\n", 0.0, fmt.clone()),
-            _ => {}
-        }
-    }
-    job
+struct Palette {
+    mode: PaletteMode,
+    query: String,
+    selected: usize,
+    /// Results of the search of `searched`, in `Search` mode.
+    hits: Vec<PaletteEntry>,
+    searched: String,
 }
 
-#[derive(Clone)]
-pub struct Icons {
-    pub search: TextureHandle,
-    pub back: TextureHandle,
-    pub home: TextureHandle,
-    pub settings: TextureHandle,
-    pub add: TextureHandle,
+/// Storage key of the last vault opened, also read by `--capture`.
+pub const VAULT_KEY: &str = "vault";
+/// Storage key of the open notes, with the vault they belong to.
+const WORKSPACE_KEY: &str = "workspace";
+/// Storage key of the preview mode and the width of its side panel.
+const PREVIEW_KEY: &str = "preview";
+
+/// At most one version of a note per interval while it is being edited.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(120);
+/// Versions older than this are pruned when the vault is opened.
+const HISTORY_MAX_AGE: i64 = 30 * 86_400;
+
+/// Automatic commits wait for this long without edits.
+const GIT_QUIET: Duration = Duration::from_secs(60);
+
+const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, KeyCode::Z);
+const REDO: [KeyboardShortcut; 2] = [
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), KeyCode::Z),
+    KeyboardShortcut::new(Modifiers::COMMAND, KeyCode::Y),
+];
+
+/// What the file dialog is currently open for, besides picking the vault.
+#[derive(Clone, Copy)]
+enum ExportTarget {
+    Note(usize, usize),
+    NotePdf(usize, usize),
+    Folder(usize),
 }
 
-impl Icons {
-    fn load(ctx: &egui::Context) -> Self {
-        fn png(bytes: &[u8], ctx: &egui::Context, id: &str) -> TextureHandle {
-            let img = image::load_from_memory(bytes).unwrap().to_rgba8();
-            let sz = [img.width() as usize, img.height() as usize];
-            ctx.load_texture(
-                id,
-                egui::ColorImage::from_rgba_unmultiplied(sz, &img),
-                egui::TextureOptions::LINEAR,
-            )
-        }
-        Self {
-            search: png(include_bytes!("../assets/search.png"), ctx, "icon_search"),
-            back: png(include_bytes!("../assets/back.png"), ctx, "icon_back"),
-            home: png(include_bytes!("../assets/home.png"), ctx, "icon_home"),
-            settings: png(
-                include_bytes!("../assets/setting.png"),
-                ctx,
-                "icon_settings",
-            ),
-            add: png(include_bytes!("../assets/add.png"), ctx, "icon_add"),
+/// Back and forward with the side buttons of the mouse.
+fn mouse_navigation(ctx: &egui::Context) -> Option<Msg> {
+    ctx.input(|i| {
+        if i.pointer.button_pressed(PointerButton::Extra1) {
+            Some(Msg::GoBack)
+        } else if i.pointer.button_pressed(PointerButton::Extra2) {
+            Some(Msg::GoForward)
+        } else {
+            None
         }
+    })
+}
+
+/// Colours of the exported pages, from the visuals of the app.
+fn export_theme(v: &egui::Visuals) -> export::Theme {
+    let color = |c: egui::Color32| Color::rgb(c.r(), c.g(), c.b());
+    export::Theme {
+        dark: v.dark_mode,
+        background: color(v.panel_fill),
+        text: color(v.text_color()),
+        strong: color(v.strong_text_color()),
+        link: color(v.hyperlink_color),
+        code_background: color(v.code_bg_color),
+        border: color(v.widgets.noninteractive.bg_stroke.color),
     }
 }
 
+/// Id of the editor of pane `pane`.
+fn editor_id(pane: usize) -> egui::Id {
+    egui::Id::new(("note_body", pane))
+}
+
 pub struct NotesApp {
     folders: Vec<Folder>,
     selected: Option<usize>,
@@ -132,103 +172,1483 @@ pub struct NotesApp {
     show_settings: bool,
     icons: Option<Icons>,
 
-    working_dir: Option<PathBuf>,
+    vault: Option<Vault>,
+    settings: Settings,
     file_dialog: FileDialog,
     dir_dialog_requested: bool,
+    pending_export: Option<ExportTarget>,
+    /// Names of the templates of the vault.
+    templates: Vec<String>,
+    /// Cursor position (in characters) to give the editor once it shows.
+    pending_cursor: Option<usize>,
+    history: Option<History>,
+    /// Last version recorded of each note edited this session.
+    snapshots: HashMap<PathBuf, Instant>,
+    history_view: Option<HistoryView>,
+    /// Git repository of the vault, if any.
+    repo: Option<Repo>,
+    /// Shown in the settings; an error when the last git command failed.
+    git_status: Option<Result<Status, String>>,
+    /// Commit or push running in the background.
+    git_job: Option<JoinHandle<std::io::Result<()>>>,
+    /// Last edit not committed yet.
+    last_edit: Option<Instant>,
+    /// Month shown by the journal calendar.
+    calendar_month: NaiveDate,
+    /// Key of the encrypted notes, while they are unlocked.
+    key: Option<Key>,
+    /// Notes whose file is encrypted. While locked, their body is the armor.
+    encrypted: HashSet<PathBuf>,
+    passphrase: String,
+    /// The new passphrase typed again, before it is chosen.
+    passphrase_confirmation: String,
+    /// Why the last passphrase was refused.
+    unlock_error: Option<String>,
+    /// Outcome of the last export, until it is dismissed.
+    export_status: Option<Result<PathBuf, String>>,
+    /// Shows the passphrase window, for an action that needs the key.
+    unlock_requested: bool,
+    /// The notes lock again after `lock_after_minutes` without input.
+    last_input: Instant,
+    /// Undo steps of the notes edited this session.
+    undo: HashMap<PathBuf, UndoStack>,
+    undo_log: Option<UndoLog>,
+    keymap: Keymap,
+    palette: Option<Palette>,
+    /// Tabs of the editor panes.
+    workspace: Workspace,
+    nav: NavHistory,
+    /// Place shown, whose editor scroll is kept up to date.
+    place: Place,
+    /// Scroll offset to give the editor once it shows.
+    pending_scroll: Option<f32>,
+    preview_mode: PreviewMode,
+    /// Width of the side panel of the preview.
+    preview_width: f32,
+    /// Between the editor of the focused pane and the side preview.
+    scroll_sync: ScrollSync,
 
     dark_mode: bool,
 
-    watcher: Option<RecommendedWatcher>,
-    rx: Option<Receiver<notify::Result<notify::Event>>>,
-    /// Ignore file system events until this time to skip our own writes.
-    ignore_fs_events_until: Option<Instant>,
+    /// Watches the vault for notes edited in other programs.
+    watcher: Option<FileWatcher>,
     md_cache: CommonMarkCache,
 }
 
 impl NotesApp {
-    pub fn new(_cc: &eframe::CreationContext) -> Self {
-        Self {
+    pub fn new(cc: &eframe::CreationContext) -> Self {
+        let mut app = Self {
             folders: Vec::new(),
             selected: None,
             selected_note: None,
             show_settings: false,
             icons: None,
 
-            working_dir: None,
+            vault: None,
+            settings: Settings::default(),
             file_dialog: FileDialog::new(),
             dir_dialog_requested: true,
+            pending_export: None,
+            templates: Vec::new(),
+            pending_cursor: None,
+            history: None,
+            snapshots: HashMap::new(),
+            history_view: None,
+            repo: None,
+            git_status: None,
+            git_job: None,
+            last_edit: None,
+            calendar_month: journal::today(),
+            key: None,
+            encrypted: HashSet::new(),
+            passphrase: String::new(),
+            passphrase_confirmation: String::new(),
+            unlock_error: None,
+            export_status: None,
+            unlock_requested: false,
+            last_input: Instant::now(),
+            undo: HashMap::new(),
+            undo_log: None,
+            keymap: Keymap::default(),
+            palette: None,
+            workspace: Workspace::default(),
+            nav: NavHistory::default(),
+            place: Place::default(),
+            pending_scroll: None,
+            preview_mode: PreviewMode::default(),
+            preview_width: 360.0,
+            scroll_sync: ScrollSync::default(),
 
             dark_mode: true,
 
             watcher: None,
-            rx: None,
-            ignore_fs_events_until: None,
             md_cache: CommonMarkCache::default(),
+        };
+        if let Some((mode, width)) = cc.storage.and_then(|s| eframe::get_value(s, PREVIEW_KEY)) {
+            app.preview_mode = mode;
+            app.preview_width = width;
+        }
+        // on rouvre le dossier de la dernière session
+        let last = cc
+            .storage
+            .and_then(|s| eframe::get_value::<PathBuf>(s, VAULT_KEY));
+        if let Some(root) = last.filter(|p| p.is_dir()) {
+            app.open_vault(root);
+            // les onglets de la dernière session, s'ils sont de ce dossier
+            let saved: Option<(PathBuf, Workspace)> =
+                cc.storage.and_then(|s| eframe::get_value(s, WORKSPACE_KEY));
+            let same_vault = |root: &PathBuf| app.vault.as_ref().is_some_and(|v| v.root() == root);
+            if let Some((_, mut workspace)) = saved.filter(|(root, _)| same_vault(root)) {
+                workspace.retain(|path| app.find_note(path).is_some());
+                app.workspace = workspace;
+                app.show_focused();
+            }
+            app.place = app.current_place();
+        }
+        app
+    }
+
+    fn open_vault(&mut self, path: PathBuf) {
+        let vault = Vault::new(path);
+        match vault.load() {
+            Ok((folders, skipped)) => {
+                self.folders = folders;
+                for (path, e) in skipped {
+                    eprintln!("{}: {e}", path.display());
+                }
+            }
+            Err(e) => eprintln!("{}: {e}", vault.root().display()),
+        }
+        self.key = None;
+        self.encrypted = self
+            .folders
+            .iter()
+            .flat_map(|f| &f.notes)
+            .filter(|n| crypt::is_encrypted(&n.body))
+            .map(|n| n.path.clone())
+            .collect();
+        self.settings = Settings::load(&vault).unwrap_or_else(|e| {
+            eprintln!("ignoring the settings of {}: {e}", vault.root().display());
+            Settings::default()
+        });
+        let (keymap, errors) = Keymap::new(&self.settings.keymap);
+        for e in errors {
+            eprintln!("keymap: {e}");
+        }
+        self.keymap = keymap;
+        self.templates = templates::names(&vault).unwrap_or_else(|e| {
+            eprintln!("cannot list the templates: {e}");
+            Vec::new()
+        });
+        let history = History::new(&vault);
+        if let Err(e) = history.prune(HISTORY_MAX_AGE, history::now()) {
+            eprintln!("cannot prune the history: {e}");
+        }
+        self.history = Some(history);
+        self.undo.clear();
+        self.undo_log = Some(UndoLog::new(&vault));
+        self.workspace = Workspace::default();
+        self.nav = NavHistory::default();
+        self.place = Place::default();
+        self.repo = Repo::open(&vault);
+        self.git_status = None;
+        self.watcher = FileWatcher::new()
+            .and_then(|mut w| w.watch(vault.root(), true).map(|_| w))
+            .map_err(|e| eprintln!("cannot watch {}: {e}", vault.root().display()))
+            .ok();
+        self.vault = Some(vault);
+    }
+
+    /// Opens the daily note of `date`, creating it (and the journal folder)
+    /// from the template on first use.
+    fn open_day(&mut self, date: NaiveDate) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let mut note = match journal::daily_note(vault, &self.settings, date, self.key.as_ref()) {
+            Ok(note) => note,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                self.unlock_requested = true;
+                return;
+            }
+            Err(e) => {
+                eprintln!("cannot open the note of {date}: {e}");
+                return;
+            }
+        };
+        if crypt::is_encrypted(&note.body) {
+            self.encrypted.insert(note.path.clone());
+            if let Some(Ok(text)) = self.key.as_ref().map(|k| k.open(&note.body)) {
+                note.body = text;
+            }
+        }
+        let dir = note.path.parent().unwrap_or(vault.root()).to_path_buf();
+        let f_idx = match self.folders.iter().position(|f| f.path == dir) {
+            Some(i) => i,
+            None => match vault::load_folder(&dir) {
+                Ok((folder, skipped)) => {
+                    for (path, e) in skipped {
+                        eprintln!("{}: {e}", path.display());
+                    }
+                    let i = self.folders.partition_point(|f| f.path < dir);
+                    self.folders.insert(i, folder);
+                    i
+                }
+                Err(e) => {
+                    eprintln!("{}: {e}", dir.display());
+                    return;
+                }
+            },
+        };
+        // les notes restent triées par nom, donc par date
+        let notes = &mut self.folders[f_idx].notes;
+        let n_idx = match notes.iter().position(|n| n.path == note.path) {
+            Some(i) => i,
+            None => {
+                let i = notes.partition_point(|n| n.path < note.path);
+                notes.insert(i, note);
+                i
+            }
+        };
+        self.selected = Some(f_idx);
+        self.selected_note = Some(n_idx);
+        self.show_settings = false;
+        self.calendar_month = date;
+    }
+
+    /// Creates a note in folder `f_idx`, from `template` if given, and opens it.
+    fn create_note(&mut self, f_idx: usize, template: Option<&str>) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let encrypt = self
+            .settings
+            .encrypted_folders
+            .contains(&self.folders[f_idx].name);
+        if encrypt && self.key.is_none() {
+            self.unlock_requested = true;
+            return;
+        }
+        let template = match template.map(|name| templates::load(vault, name)) {
+            Some(Ok(Some(text))) => Some(text),
+            Some(Ok(None)) | None => None,
+            Some(Err(e)) => {
+                eprintln!("cannot read the template: {e}");
+                None
+            }
+        };
+        let folder = &mut self.folders[f_idx];
+        let rel = vault.relative(&folder.path);
+        // premier numéro libre
+        let mut n = folder.notes.len() + 1;
+        let created = loop {
+            let title = format!("Nouvelle note {n}");
+            let (body, cursor) = match &template {
+                Some(text) => templates::expand(text, &Vars::now(&title, &folder.name)),
+                None => (String::new(), None),
+            };
+            let text = match self.key.as_ref().filter(|_| encrypt) {
+                Some(key) => match key.seal(&body) {
+                    Ok(armor) => armor,
+                    Err(e) => break Err(e),
+                },
+                None => body.clone(),
+            };
+            match vault.create_note(&rel, &title, &text) {
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+                result => break result.map(|note| (Note { body, ..note }, cursor)),
+            }
+        };
+        match created {
+            Ok((note, cursor)) => {
+                if encrypt {
+                    self.encrypted.insert(note.path.clone());
+                }
+                folder.notes.push(note);
+                self.selected_note = Some(folder.notes.len() - 1);
+                self.pending_cursor = cursor;
+                self.last_edit = Some(Instant::now());
+            }
+            Err(e) => eprintln!("cannot create a note in {rel}: {e}"),
+        }
+    }
+
+    /// Writes note `n_idx` of folder `f_idx` after an edit. Before that, the
+    /// file is kept as a version if none was recorded for a while, or if the
+    /// edit removed more than half of the text.
+    fn save_note(&mut self, f_idx: usize, n_idx: usize, len_before: usize) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let note = &self.folders[f_idx].notes[n_idx];
+        let rel = vault.relative(&note.path);
+        if let Some(history) = &self.history {
+            let due = self
+                .snapshots
+                .get(&note.path)
+                .is_none_or(|t| t.elapsed() >= SNAPSHOT_INTERVAL);
+            if due || note.body.len() < len_before / 2 {
+                let recorded = match fs::read_to_string(&note.path) {
+                    Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+                    read => read.and_then(|old| history.snapshot(&rel, &old, history::now())),
+                };
+                if let Err(e) = recorded {
+                    eprintln!("cannot keep a version of {rel}: {e}");
+                }
+                self.snapshots.insert(note.path.clone(), Instant::now());
+            }
+        }
+        if let Err(e) = self
+            .file_text(note)
+            .and_then(|text| vault.write_note(&rel, &text))
+        {
+            eprintln!("cannot save {rel}: {e}");
+        }
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// What the file of `note` holds: its body, sealed when it is encrypted.
+    /// Never the plaintext of an encrypted note, even without the key.
+    fn file_text<'a>(&self, note: &'a Note) -> io::Result<Cow<'a, str>> {
+        if !self.encrypted.contains(&note.path) {
+            return Ok(Cow::Borrowed(&note.body));
+        }
+        match &self.key {
+            Some(key) if !crypt::is_encrypted(&note.body) => key.seal(&note.body).map(Cow::Owned),
+            _ => Err(crypt::locked()),
+        }
+    }
+
+    /// Whether the text of `note` is not available: encrypted and locked,
+    /// or encrypted with another passphrase.
+    fn is_locked(&self, note: &Note) -> bool {
+        self.encrypted.contains(&note.path)
+            && (self.key.is_none() || crypt::is_encrypted(&note.body))
+    }
+
+    /// Derives the key from the passphrase typed and decrypts the notes.
+    fn unlock(&mut self) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let unlocked = if !crypt::has_key(vault) && self.passphrase != self.passphrase_confirmation
+        {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Les deux phrases de passe diffèrent.",
+            ))
+        } else {
+            Key::unlock(vault, &self.passphrase)
+        };
+        self.passphrase.zeroize();
+        self.passphrase_confirmation.zeroize();
+        let key = match unlocked {
+            Ok(key) => key,
+            Err(e) => {
+                self.unlock_error = Some(match e.kind() {
+                    ErrorKind::PermissionDenied => "Phrase de passe incorrecte.".to_owned(),
+                    _ => e.to_string(),
+                });
+                return;
+            }
+        };
+        for note in self.folders.iter_mut().flat_map(|f| f.notes.iter_mut()) {
+            if !self.encrypted.contains(&note.path) || !crypt::is_encrypted(&note.body) {
+                continue;
+            }
+            match key.open(&note.body) {
+                Ok(text) => note.body = text,
+                Err(e) => eprintln!("cannot decrypt {}: {e}", note.path.display()),
+            }
+        }
+        self.key = Some(key);
+        self.unlock_error = None;
+        self.unlock_requested = false;
+        self.last_input = Instant::now();
+    }
+
+    /// Forgets the key and the plaintext of the encrypted notes.
+    fn lock(&mut self) {
+        self.key = None;
+        for note in self.folders.iter_mut().flat_map(|f| f.notes.iter_mut()) {
+            if self.encrypted.contains(&note.path) {
+                note.body.zeroize();
+                note.body = fs::read_to_string(&note.path).unwrap_or_default();
+                if let Some(mut stack) = self.undo.remove(&note.path) {
+                    stack.forget();
+                }
+            }
+        }
+        if let Some(mut view) = self
+            .history_view
+            .take_if(|v| self.encrypted.contains(&v.path))
+        {
+            view.text.zeroize();
+        }
+    }
+
+    /// Locks once nothing was typed or clicked for `lock_after_minutes`.
+    fn auto_lock(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.events.is_empty()) {
+            self.last_input = Instant::now();
+        }
+        if self.key.is_none() || self.settings.lock_after_minutes == 0 {
+            return;
+        }
+        let idle = Duration::from_secs(60 * u64::from(self.settings.lock_after_minutes));
+        match idle.checked_sub(self.last_input.elapsed()) {
+            Some(wait) if !wait.is_zero() => ctx.request_repaint_after(wait),
+            _ => self.lock(),
+        }
+    }
+
+    /// Encrypts (`on`) or decrypts the file of note `n_idx` of folder `f_idx`.
+    /// Its local versions are forgotten once it is encrypted, as they are in
+    /// clear; the commits of a git repository are not rewritten.
+    fn set_encrypted(&mut self, f_idx: usize, n_idx: usize, on: bool) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let Some(key) = &self.key else {
+            self.unlock_requested = true;
+            return;
+        };
+        let note = &self.folders[f_idx].notes[n_idx];
+        if self.encrypted.contains(&note.path) == on || crypt::is_encrypted(&note.body) {
+            return;
+        }
+        let rel = vault.relative(&note.path);
+        let text = if on {
+            key.seal(&note.body)
+        } else {
+            Ok(note.body.clone())
+        };
+        if let Err(e) = text.and_then(|text| vault.write_note(&rel, &text)) {
+            eprintln!("cannot save {rel}: {e}");
+            return;
+        }
+        if !on {
+            self.encrypted.remove(&note.path);
+            return;
+        }
+        self.encrypted.insert(note.path.clone());
+        if let Err(e) = self.history.as_ref().map_or(Ok(()), |h| h.forget(&rel)) {
+            eprintln!("cannot forget the versions of {rel}: {e}");
+        }
+        if let Err(e) = self
+            .undo_log
+            .as_ref()
+            .map_or(Ok(()), |log| log.remove(&rel))
+        {
+            eprintln!("cannot forget the undo steps of {rel}: {e}");
+        }
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// Undo steps of the note at `path`, whose text is `text`. They are read
+    /// from the undo log on first use, when it is kept between sessions.
+    fn undo_stack(&mut self, path: &Path, text: &str) -> &mut UndoStack {
+        let log = self
+            .undo_log
+            .as_ref()
+            .filter(|_| self.settings.persistent_undo && !self.encrypted.contains(path));
+        let vault = &self.vault;
+        self.undo
+            .entry(path.to_path_buf())
+            .or_insert_with(|| match (log, vault) {
+                (Some(log), Some(vault)) => {
+                    log.load(&vault.relative(path), text).unwrap_or_else(|e| {
+                        eprintln!("ignoring the undo steps of {}: {e}", path.display());
+                        UndoStack::default()
+                    })
+                }
+                _ => UndoStack::default(),
+            })
+    }
+
+    /// Undoes (or redoes) the last step of the open note, then saves it.
+    fn undo_step(&mut self, redo: bool) {
+        let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) else {
+            return;
+        };
+        let note = &self.folders[f_idx].notes[n_idx];
+        if self.is_locked(note) {
+            return;
+        }
+        let (path, mut body) = (note.path.clone(), note.body.clone());
+        let len_before = body.len();
+        let stack = self.undo_stack(&path, &body);
+        let cursor = if redo {
+            stack.redo(&mut body)
+        } else {
+            stack.undo(&mut body)
+        };
+        if let Some(at) = cursor {
+            self.pending_cursor = Some(body[..at].chars().count());
+            self.folders[f_idx].notes[n_idx].body = body;
+            self.save_note(f_idx, n_idx, len_before);
+        }
+    }
+
+    /// Writes the undo steps changed since the last call, if they are kept
+    /// between sessions. Never those of encrypted notes.
+    fn save_undo(&mut self) {
+        let (Some(vault), Some(log)) = (&self.vault, &self.undo_log) else {
+            return;
+        };
+        if !self.settings.persistent_undo {
+            return;
+        }
+        for note in self.folders.iter().flat_map(|f| &f.notes) {
+            if self.encrypted.contains(&note.path) {
+                continue;
+            }
+            if let Some(stack) = self.undo.get_mut(&note.path).filter(|s| s.is_dirty()) {
+                let rel = vault.relative(&note.path);
+                if let Err(e) = log.save(&rel, stack, &note.body) {
+                    eprintln!("cannot save the undo steps of {rel}: {e}");
+                }
+            }
+        }
+    }
+
+    /// Selects the note of the focused pane, if it has one.
+    fn show_focused(&mut self) {
+        match self
+            .workspace
+            .focused()
+            .current()
+            .and_then(|path| self.find_note(path))
+        {
+            Some((f_idx, n_idx)) => {
+                self.selected = Some(f_idx);
+                self.selected_note = Some(n_idx);
+                self.show_settings = false;
+            }
+            None => self.selected_note = None,
+        }
+    }
+
+    /// Opens the selected note in the focused pane, however it was selected.
+    fn sync_tabs(&mut self) {
+        if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
+            let path = &self.folders[f_idx].notes[n_idx].path;
+            if self.workspace.focused().current() != Some(path) {
+                self.workspace.focused_mut().open(path);
+            }
+        }
+    }
+
+    fn current_place(&self) -> Place {
+        Place::of(&self.folders, self.selected, self.selected_note)
+    }
+
+    /// Records the place left, whatever took the user elsewhere.
+    fn track_place(&mut self) {
+        let now = self.current_place();
+        if !now.same(&self.place) {
+            let left = std::mem::replace(&mut self.place, now);
+            self.nav.visit(left);
+            self.scroll_sync = ScrollSync::default();
+        }
+    }
+
+    /// Back or forward in the places viewed. Back with nowhere to go back to
+    /// goes up: from a note to its folder, from a folder to home.
+    fn navigate(&mut self, forward: bool) {
+        let current = self.place.clone();
+        let place = match forward {
+            true => self.nav.forward(current),
+            false => self.nav.back(current),
+        };
+        let Some(place) = place else {
+            if forward {
+                return;
+            } else if self.selected_note.is_some() {
+                self.selected_note = None;
+            } else {
+                self.selected = None;
+            }
+            self.show_settings = false;
+            return;
+        };
+        (self.selected, self.selected_note) = place.locate(&self.folders);
+        self.show_settings = false;
+        self.pending_scroll = Some(place.scroll);
+        self.place = Place {
+            scroll: place.scroll,
+            ..self.current_place()
+        };
+    }
+
+    /// The note a wiki-link points to: same file name, or else same title.
+    fn link_target(&self, target: &str) -> Option<PathBuf> {
+        let target = target.trim().to_lowercase();
+        let notes = || self.folders.iter().flat_map(|f| &f.notes);
+        notes()
+            .find(|n| {
+                n.path
+                    .file_stem()
+                    .is_some_and(|s| s.to_string_lossy().to_lowercase() == target)
+            })
+            .or_else(|| notes().find(|n| n.title.to_lowercase() == target))
+            .map(|n| n.path.clone())
+    }
+
+    /// Folder and index of the note at `path`.
+    fn find_note(&self, path: &Path) -> Option<(usize, usize)> {
+        self.folders.iter().enumerate().find_map(|(f, folder)| {
+            folder
+                .notes
+                .iter()
+                .position(|n| n.path == path)
+                .map(|n| (f, n))
+        })
+    }
+
+    /// What `action` does here, `None` where it makes no sense (exporting
+    /// without an open note…).
+    fn action_msg(&self, action: Action) -> Option<Msg> {
+        let note_open = self.selected_note.is_some();
+        Some(match action {
+            Action::CommandPalette => Msg::OpenPalette(PaletteMode::Commands),
+            Action::QuickOpen => Msg::OpenPalette(PaletteMode::Notes),
+            Action::Search => Msg::OpenPalette(PaletteMode::Search),
+            Action::NewNote if self.selected.is_some() => Msg::CreateItem,
+            Action::Back => Msg::GoBack,
+            Action::Forward if self.nav.can_go_forward() => Msg::GoForward,
+            Action::Home => Msg::SelectHome,
+            Action::Settings => Msg::OpenSettings,
+            Action::Today => Msg::OpenDay(journal::today()),
+            Action::PreviousDay => Msg::PreviousDay,
+            Action::NextDay => Msg::NextDay,
+            Action::History if note_open => Msg::OpenHistory,
+            Action::ExportHtml if note_open => Msg::ExportNote,
+            Action::ExportPdf if note_open => Msg::ExportNotePdf,
+            Action::ExportFolder if self.selected.is_some() => Msg::ExportFolder,
+            Action::Lock if self.key.is_some() => Msg::Lock,
+            Action::CloseTab => {
+                let pane = self.workspace.focused();
+                pane.current()?;
+                Msg::CloseTab(self.workspace.focused, pane.active)
+            }
+            Action::SplitView => Msg::ToggleSplit,
+            Action::PreviewMode => Msg::SetPreviewMode(self.preview_mode.next()),
+            Action::GitCommit if self.repo.is_some() => Msg::GitCommit,
+            Action::GitPush if self.repo.is_some() => Msg::GitPush,
+            _ => return None,
+        })
+    }
+
+    /// The action of the shortcut pressed, if any. Shortcuts without Ctrl are
+    /// left to the text fields while one has the focus (Alt+← jumps a word).
+    fn keymap_shortcut(&self, ctx: &egui::Context) -> Option<Msg> {
+        let typing = ctx.wants_keyboard_input();
+        let action = ctx.input_mut(|i| {
+            self.keymap
+                .bindings()
+                .iter()
+                .find(|(shortcut, _)| {
+                    (!typing || shortcut.modifiers.command) && i.consume_shortcut(shortcut)
+                })
+                .map(|(_, action)| *action)
+        })?;
+        self.action_msg(action)
+    }
+
+    /// Lines of the palette for its mode and query, the best matches first.
+    fn palette_entries(&self, palette: &Palette) -> Vec<PaletteEntry> {
+        if palette.mode == PaletteMode::Search {
+            return palette.hits.clone();
+        }
+        let mut entries = Vec::new();
+        if palette.mode == PaletteMode::Commands {
+            for action in Action::ALL {
+                if let Some(msg) = self.action_msg(action) {
+                    let detail = self.keymap.shortcut(action).map(|shortcut| {
+                        shortcut.format(&egui::ModifierNames::NAMES, cfg!(target_os = "macos"))
+                    });
+                    let label = action.label().to_owned();
+                    entries.push(PaletteEntry {
+                        label,
+                        detail: detail.unwrap_or_default(),
+                        msg,
+                    });
+                }
+            }
+        }
+        for folder in &self.folders {
+            for note in &folder.notes {
+                entries.push(PaletteEntry {
+                    label: note.title.clone(),
+                    detail: folder.name.clone(),
+                    msg: Msg::OpenNote(note.path.clone()),
+                });
+            }
+        }
+        let mut scored: Vec<(i32, PaletteEntry)> = entries
+            .into_iter()
+            .filter_map(|e| {
+                let score = fuzzy::score(&palette.query, &format!("{} {}", e.label, e.detail))?;
+                Some((score, e))
+            })
+            .collect();
+        // tri stable : sans requête, l'ordre d'origine
+        if !palette.query.trim().is_empty() {
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+        scored.into_iter().map(|(_, e)| e).take(100).collect()
+    }
+
+    /// Runs the search of the palette again once its query changed.
+    fn refresh_search(&mut self) {
+        let (Some(vault), Some(palette)) = (&self.vault, &mut self.palette) else {
+            return;
+        };
+        if palette.mode != PaletteMode::Search || palette.searched == palette.query {
+            return;
+        }
+        palette.searched = palette.query.clone();
+        palette.hits = match vault.search(&palette.query) {
+            Ok(hits) => hits
+                .into_iter()
+                .map(|hit| PaletteEntry {
+                    label: hit.note.title,
+                    detail: hit.snippet,
+                    msg: Msg::OpenNote(hit.note.path),
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("search failed: {e}");
+                Vec::new()
+            }
+        };
+    }
+
+    /// Ctrl+Z and Ctrl+Shift+Z (or Ctrl+Y) of the note editor, taken before
+    /// the `TextEdit` sees them: its own undo is lost with each note switch.
+    fn undo_shortcut(&self, ctx: &egui::Context) -> Option<Msg> {
+        self.selected_note?;
+        if ctx
+            .memory(|m| m.focused())
+            .is_some_and(|id| id != editor_id(self.workspace.focused))
+        {
+            return None;
+        }
+        ctx.input_mut(|i| {
+            if REDO.iter().any(|shortcut| i.consume_shortcut(shortcut)) {
+                Some(Msg::Redo)
+            } else if i.consume_shortcut(&UNDO) {
+                Some(Msg::Undo)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Commits (if `commit`) then pushes (if `push`) in the background.
+    fn start_git_job(&mut self, commit: bool, push: bool) {
+        let Some(repo) = self.repo.clone() else {
+            return;
+        };
+        if self.git_job.is_some() {
+            return;
+        }
+        self.git_job = Some(thread::spawn(move || {
+            if commit {
+                repo.commit_all()?;
+            }
+            if push {
+                repo.push()?;
+            }
+            Ok(())
+        }));
+    }
+
+    /// Commits once edits have stopped for `GIT_QUIET`, and collects the
+    /// result of the background job.
+    fn auto_commit(&mut self, ctx: &egui::Context) {
+        if self.git_job.as_ref().is_some_and(JoinHandle::is_finished) {
+            let result = self.git_job.take().map(JoinHandle::join);
+            self.git_status = match result {
+                Some(Ok(Err(e))) => Some(Err(e.to_string())),
+                Some(Err(_)) => Some(Err("git job panicked".into())),
+                _ => self
+                    .repo
+                    .as_ref()
+                    .map(|r| r.status().map_err(|e| e.to_string())),
+            };
+            if let Some(Err(e)) = &self.git_status {
+                eprintln!("{e}");
+            }
+        } else if self.git_job.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+        if !self.settings.git_auto_commit || self.repo.is_none() {
+            return;
         }
+        let Some(edited) = self.last_edit else {
+            return;
+        };
+        match GIT_QUIET.checked_sub(edited.elapsed()) {
+            Some(wait) if !wait.is_zero() => ctx.request_repaint_after(wait),
+            _ if self.git_job.is_none() => {
+                self.last_edit = None;
+                self.start_git_job(true, self.settings.git_push);
+            }
+            _ => {}
+        }
+    }
+
+    /// The day of the open note, when it is a daily note.
+    fn current_day(&self) -> Option<NaiveDate> {
+        let folder = &self.folders[self.selected?];
+        if !journal::is_journal(&self.settings, folder) {
+            return None;
+        }
+        journal::date_of(&folder.notes[self.selected_note?])
+    }
+
+    /// Opens the closest daily note before or after the open one (or today).
+    fn step_day(&mut self, forward: bool) {
+        let from = self.current_day().unwrap_or_else(journal::today);
+        let days = self
+            .folders
+            .iter()
+            .find(|f| journal::is_journal(&self.settings, f))
+            .map(journal::days)
+            .unwrap_or_default();
+        if let Some(day) = journal::neighbour(&days, from, forward) {
+            self.open_day(day);
+        }
+    }
+
+    /// Calendar of the journal folder `f_idx`, on the month shown.
+    fn calendar(&self, ui: &mut egui::Ui, f_idx: usize) -> Option<Msg> {
+        let days = journal::days(&self.folders[f_idx]);
+        Calendar::new(
+            self.calendar_month,
+            &days,
+            journal::today(),
+            self.current_day(),
+        )
+        .render(ui)
     }
 
-    fn watch(&mut self, path: &PathBuf) {
-        if self.watcher.is_none() {
-            let (tx, rx) = channel();
-            let mut watcher = RecommendedWatcher::new(tx, Config::default()).ok();
-            if let Some(w) = watcher.as_mut() {
-                let _ = w.watch(path, RecursiveMode::NonRecursive);
+    /// Reloads the notes changed on disk, as steps that can be undone. Our
+    /// own writes come back as events too, but then the file holds what is
+    /// already in memory.
+    fn reload_changed(&mut self) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        for path in watcher.changes() {
+            let found = self.find_note(&path);
+            let (Some((f_idx, n_idx)), Ok(mut text)) = (found, fs::read_to_string(&path)) else {
+                continue;
+            };
+            if crypt::is_encrypted(&text) {
+                // déverrouillé, on compare le texte en clair
+                if let Some(Ok(plain)) = self.key.as_ref().map(|k| k.open(&text)) {
+                    text = plain;
+                }
+                self.encrypted.insert(path.clone());
+            } else {
+                self.encrypted.remove(&path);
+            }
+            let body = &self.folders[f_idx].notes[n_idx].body;
+            if *body == text {
+                continue;
             }
-            self.watcher = watcher;
-            self.rx = Some(rx);
+            if !crypt::is_encrypted(body) && !crypt::is_encrypted(&text) {
+                let old = body.clone();
+                self.undo_stack(&path, &old).record(&old, &text, false);
+            }
+            let note = &mut self.folders[f_idx].notes[n_idx];
+            note.body.zeroize();
+            note.body = text;
         }
     }
 
     fn handle(&mut self, msg: Msg) {
         match msg {
             Msg::SelectFolder(i) => {
+                // les modèles ont pu changer sur le disque
+                if let Some(vault) = &self.vault {
+                    self.templates = templates::names(vault).unwrap_or_default();
+                }
                 self.selected = Some(i);
                 self.selected_note = None;
                 self.show_settings = false;
             }
+            Msg::SelectNote(i) => {
+                self.selected_note = Some(i);
+            }
             Msg::SelectHome => {
                 self.selected = None;
                 self.selected_note = None;
                 self.show_settings = false;
             }
-            Msg::GoBack => {
-                if self.selected_note.is_some() {
-                    self.selected_note = None;
-                } else {
-                    self.selected = None;
-                }
-                self.show_settings = false;
-            }
+            Msg::GoBack => self.navigate(false),
+            Msg::GoForward => self.navigate(true),
             Msg::OpenSettings => {
+                self.git_status = self
+                    .repo
+                    .as_ref()
+                    .map(|r| r.status().map_err(|e| e.to_string()));
                 self.show_settings = true;
                 self.selected = None;
                 self.selected_note = None;
             }
             Msg::CreateItem => {
+                // dans le journal, + ouvre la note du jour
+                if self
+                    .selected
+                    .is_some_and(|i| journal::is_journal(&self.settings, &self.folders[i]))
+                {
+                    self.open_day(journal::today());
+                    return;
+                }
                 if let Some(f_idx) = self.selected {
-                    let name = format!("Nouvelle note {}", self.folders[f_idx].notes.len() + 1);
-                    let path = self.folders[f_idx]
+                    let template = self
+                        .settings
+                        .folder_templates
+                        .get(&self.folders[f_idx].name);
+                    self.create_note(f_idx, template.cloned().as_deref());
+                } else if let Some(vault) = &self.vault {
+                    let mut n = self.folders.len() + 1;
+                    let created = loop {
+                        match vault.create_folder(&format!("Nouveau dossier {n}")) {
+                            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+                            result => break result,
+                        }
+                    };
+                    match created {
+                        Ok(folder) => self.folders.push(folder),
+                        Err(e) => eprintln!("cannot create a folder: {e}"),
+                    }
+                }
+            }
+            Msg::CreateNote(template) => {
+                if let Some(f_idx) = self.selected {
+                    self.create_note(f_idx, template.as_deref());
+                }
+            }
+            Msg::ExportNote => {
+                if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
+                    let note = &self.folders[f_idx].notes[n_idx];
+                    self.file_dialog.config_mut().default_file_name = export::html_file_name(note);
+                    self.file_dialog.save_file();
+                    self.pending_export = Some(ExportTarget::Note(f_idx, n_idx));
+                }
+            }
+            Msg::ExportNotePdf => {
+                if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
+                    let note = &self.folders[f_idx].notes[n_idx];
+                    self.file_dialog.config_mut().default_file_name = note
                         .path
-                        .join(format!("note_{}.md", self.folders[f_idx].notes.len() + 1));
-                    let _ = fs::write(&path, "");
-                    self.folders[f_idx].notes.push(Note {
-                        title: name.clone(),
-                        body: String::new(),
-                        path,
-                    });
-                    self.selected_note = Some(self.folders[f_idx].notes.len() - 1);
-                } else if let Some(dir) = &self.working_dir {
-                    let name = format!("Nouveau dossier {}", self.folders.len() + 1);
-                    let path = dir.join(&name);
-                    let _ = fs::create_dir_all(&path);
-                    self.folders.push(Folder {
-                        name,
-                        color: Color32::from_rgb(100, 100, 200),
-                        notes: Vec::new(),
-                        path,
+                        .with_extension("pdf")
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    self.file_dialog.save_file();
+                    self.pending_export = Some(ExportTarget::NotePdf(f_idx, n_idx));
+                }
+            }
+            Msg::ExportFolder => {
+                if let Some(f_idx) = self.selected {
+                    self.file_dialog.pick_directory();
+                    self.pending_export = Some(ExportTarget::Folder(f_idx));
+                }
+            }
+            Msg::ChooseVault => {
+                self.vault = None;
+                self.watcher = None;
+                self.history = None;
+                self.history_view = None;
+                self.save_undo();
+                self.undo_log = None;
+                self.workspace = Workspace::default();
+                self.nav = NavHistory::default();
+                self.place = Place::default();
+                self.repo = None;
+                self.git_status = None;
+                self.lock();
+                self.encrypted.clear();
+                self.folders.clear();
+                self.selected = None;
+                self.selected_note = None;
+                self.show_settings = false;
+                self.dir_dialog_requested = true;
+            }
+            Msg::SaveSettings => {
+                if let Some(vault) = &self.vault {
+                    if let Err(e) = self.settings.save(vault) {
+                        eprintln!("cannot save the settings: {e}");
+                    }
+                }
+            }
+            Msg::OpenDay(date) => self.open_day(date),
+            Msg::PreviousDay => self.step_day(false),
+            Msg::NextDay => self.step_day(true),
+            Msg::ShowMonth(month) => self.calendar_month = month,
+            Msg::OpenHistory => {
+                let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) else {
+                    return;
+                };
+                let (Some(vault), Some(history)) = (&self.vault, &self.history) else {
+                    return;
+                };
+                let path = self.folders[f_idx].notes[n_idx].path.clone();
+                let rel = vault.relative(&path);
+                let versions = history.versions(&rel).unwrap_or_else(|e| {
+                    eprintln!("cannot read the history of {rel}: {e}");
+                    Vec::new()
+                });
+                let commits = match &self.repo {
+                    Some(repo) => repo.log(&rel).unwrap_or_else(|e| {
+                        eprintln!("{e}");
+                        Vec::new()
+                    }),
+                    None => Vec::new(),
+                };
+                self.history_view = Some(HistoryView {
+                    path,
+                    git: false,
+                    versions,
+                    commits,
+                    selected: None,
+                    text: String::new(),
+                });
+            }
+            Msg::SelectVersion(idx) => {
+                let (Some(vault), Some(history)) = (&self.vault, &self.history) else {
+                    return;
+                };
+                let Some(view) = &mut self.history_view else {
+                    return;
+                };
+                let text = match (&self.repo, view.git) {
+                    (Some(repo), true) => {
+                        repo.show(&view.commits[idx], &vault.relative(&view.path))
+                    }
+                    _ => history.read(&view.versions[idx]),
+                };
+                let text = text.and_then(|text| match &self.key {
+                    _ if !crypt::is_encrypted(&text) => Ok(text),
+                    Some(key) => key.open(&text),
+                    None => Err(crypt::locked()),
+                });
+                match text {
+                    Ok(text) => {
+                        view.selected = Some(idx);
+                        view.text = text;
+                    }
+                    Err(e) => eprintln!("cannot read this version: {e}"),
+                }
+            }
+            Msg::ShowGitHistory(git) => {
+                if let Some(view) = &mut self.history_view {
+                    view.git = git;
+                    view.selected = None;
+                    view.text.clear();
+                }
+            }
+            Msg::GitCommit => self.start_git_job(true, self.settings.git_push),
+            Msg::GitPush => self.start_git_job(false, true),
+            Msg::GitRefresh => {
+                self.git_status = self
+                    .repo
+                    .as_ref()
+                    .map(|r| r.status().map_err(|e| e.to_string()));
+            }
+            Msg::RestoreVersion => {
+                let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) else {
+                    return;
+                };
+                let (Some(vault), Some(history)) = (&self.vault, &self.history) else {
+                    return;
+                };
+                let Some(view) = self.history_view.take() else {
+                    return;
+                };
+                let note = &mut self.folders[f_idx].notes[n_idx];
+                let rel = vault.relative(&note.path);
+                // le texte remplacé reste dans l'historique, tel qu'il est sur
+                // le disque : chiffré pour une note chiffrée
+                let recorded = match fs::read_to_string(&note.path) {
+                    Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+                    read => read.and_then(|old| history.snapshot(&rel, &old, history::now())),
+                };
+                if let Err(e) = recorded {
+                    eprintln!("cannot keep a version of {rel}: {e}");
+                    return;
+                }
+                let (path, old) = (
+                    note.path.clone(),
+                    std::mem::replace(&mut note.body, view.text),
+                );
+                let note = &self.folders[f_idx].notes[n_idx];
+                if let Err(e) = self
+                    .file_text(note)
+                    .and_then(|text| vault.write_note(&rel, &text))
+                {
+                    eprintln!("cannot save {rel}: {e}");
+                }
+                let new = note.body.clone();
+                self.undo_stack(&path, &old).record(&old, &new, false);
+                self.last_edit = Some(Instant::now());
+            }
+            Msg::CloseHistory => self.history_view = None,
+            Msg::Unlock => self.unlock(),
+            Msg::CancelUnlock => {
+                self.passphrase.zeroize();
+                self.passphrase_confirmation.zeroize();
+                self.unlock_error = None;
+                self.unlock_requested = false;
+            }
+            Msg::Lock => self.lock(),
+            Msg::Encrypt(on) => {
+                if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
+                    self.set_encrypted(f_idx, n_idx, on);
+                }
+            }
+            Msg::OpenPalette(mode) => {
+                // le même raccourci referme la palette
+                self.palette = match &self.palette {
+                    Some(palette) if palette.mode == mode => None,
+                    _ => Some(Palette {
+                        mode,
+                        query: String::new(),
+                        selected: 0,
+                        hits: Vec::new(),
+                        searched: String::new(),
+                    }),
+                };
+            }
+            Msg::ClosePalette => self.palette = None,
+            Msg::OpenNote(path) => {
+                if let Some((f_idx, n_idx)) = self.find_note(&path) {
+                    self.selected = Some(f_idx);
+                    self.selected_note = Some(n_idx);
+                    self.show_settings = false;
+                }
+            }
+            Msg::OpenLink(target) => match self.link_target(&target) {
+                Some(path) => self.handle(Msg::OpenNote(path)),
+                None => eprintln!("no note named {target}"),
+            },
+            Msg::SetPreviewMode(mode) => {
+                self.preview_mode = mode;
+                self.scroll_sync = ScrollSync::default();
+            }
+            Msg::SelectTab(pane, idx) => {
+                self.workspace.focused = pane;
+                self.workspace.focused_mut().active = idx;
+                self.show_focused();
+            }
+            Msg::CloseTab(pane, idx) => {
+                self.workspace.close(pane, idx);
+                self.show_focused();
+            }
+            Msg::MoveTab(pane, from, to) => self.workspace.panes[pane].move_tab(from, to),
+            Msg::FocusPane(pane) => {
+                self.workspace.focused = pane;
+                self.show_focused();
+            }
+            Msg::ToggleSplit => {
+                self.workspace.toggle_split();
+                self.show_focused();
+            }
+            Msg::Undo => self.undo_step(false),
+            Msg::Redo => self.undo_step(true),
+            Msg::EncryptFolder(on) => {
+                let Some(f_idx) = self.selected else {
+                    return;
+                };
+                if self.key.is_none() {
+                    self.unlock_requested = true;
+                    return;
+                }
+                let name = self.folders[f_idx].name.clone();
+                if on {
+                    self.settings.encrypted_folders.insert(name);
+                } else {
+                    self.settings.encrypted_folders.remove(&name);
+                }
+                self.handle(Msg::SaveSettings);
+                for n_idx in 0..self.folders[f_idx].notes.len() {
+                    self.set_encrypted(f_idx, n_idx, on);
+                }
+            }
+        }
+    }
+
+    /// Titles of the tabs of pane `pane`.
+    fn tab_titles(&self, pane: usize) -> Vec<String> {
+        let title = |path: &PathBuf| match self.find_note(path) {
+            Some((f, n)) => self.folders[f].notes[n].title.clone(),
+            None => path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        self.workspace.panes[pane].tabs.iter().map(title).collect()
+    }
+
+    /// Tabs and note of pane `pane`.
+    fn pane_view(&mut self, ui: &mut egui::Ui, pane: usize) -> Option<Msg> {
+        let titles = self.tab_titles(pane);
+        let split = (pane == 0).then(|| self.workspace.is_split());
+        let focused = pane == self.workspace.focused && self.workspace.is_split();
+        let active = self.workspace.panes[pane].active;
+        let mut msg = TabBar::new(&titles, active, pane, focused, split).render(ui);
+        ui.separator();
+        let current = self.workspace.panes[pane]
+            .current()
+            .and_then(|path| self.find_note(path));
+        let Some((f_idx, n_idx)) = current else {
+            ui.colored_label(egui::Color32::GRAY, "Aucune note ouverte.");
+            return msg;
+        };
+        if let Some(m) = self.note_view(ui, f_idx, n_idx, pane) {
+            msg = Some(m);
+        }
+        msg
+    }
+
+    /// Title, tools and editor of note `n_idx` of folder `f_idx`, in pane
+    /// `pane`; each pane has its own cursor and scroll position.
+    fn note_view(
+        &mut self,
+        ui: &mut egui::Ui,
+        f_idx: usize,
+        n_idx: usize,
+        pane: usize,
+    ) -> Option<Msg> {
+        let mut pending = None;
+        let focused = pane == self.workspace.focused;
+        if focused && self.current_day().is_some() {
+            egui::CollapsingHeader::new("Calendrier")
+                .id_salt(("calendar", pane))
+                .show(ui, |ui| {
+                    pending = self.calendar(ui, f_idx);
+                });
+        }
+        let locked = self.is_locked(&self.folders[f_idx].notes[n_idx]);
+        let note = &mut self.folders[f_idx].notes[n_idx];
+        let encrypted = self.encrypted.contains(&note.path);
+
+        // édition du titre (single-line)
+        ui.horizontal(|ui| {
+            let title = TextEdit::singleline(&mut note.title).hint_text("Titre de la note");
+            if ui
+                .add(title.id(egui::Id::new(("note_title", pane))))
+                .gained_focus()
+                && !focused
+            {
+                pending = Some(Msg::FocusPane(pane));
+            }
+            ui.add_enabled_ui(focused, |ui| {
+                if ui.button("Historique").clicked() {
+                    pending = Some(Msg::OpenHistory);
+                }
+                if !encrypted && ui.button("🔒 Chiffrer").clicked() {
+                    pending = Some(Msg::Encrypt(true));
+                }
+                if encrypted && !locked && ui.button("🔓 Ne plus chiffrer").clicked() {
+                    pending = Some(Msg::Encrypt(false));
+                }
+                if encrypted && self.key.is_some() && ui.button("Verrouiller").clicked() {
+                    pending = Some(Msg::Lock);
+                }
+                ui.menu_button("Exporter", |ui| {
+                    if ui.button("HTML…").clicked() {
+                        pending = Some(Msg::ExportNote);
+                        ui.close_menu();
+                    }
+                    if ui.button("PDF…").clicked() {
+                        pending = Some(Msg::ExportNotePdf);
+                        ui.close_menu();
+                    }
+                });
+                let mut mode = self.preview_mode;
+                egui::ComboBox::from_id_salt(("preview_mode", pane))
+                    .selected_text(mode.label())
+                    .show_ui(ui, |ui| {
+                        for m in PreviewMode::ALL {
+                            ui.selectable_value(&mut mode, m, m.label());
+                        }
                     });
+                if mode != self.preview_mode {
+                    pending = Some(Msg::SetPreviewMode(mode));
+                }
+            });
+        });
+        ui.add_space(8.0);
+
+        if locked {
+            ui.heading("🔒 Note chiffrée");
+            if self.key.is_some() {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    "Cette note n’a pas pu être déchiffrée avec cette phrase de passe.",
+                );
+            } else {
+                let first = self.vault.as_ref().is_some_and(|v| !crypt::has_key(v));
+                let confirmation = first.then_some(&mut self.passphrase_confirmation);
+                let error = self.unlock_error.as_deref();
+                if let Some(msg) =
+                    UnlockPanel::new(&mut self.passphrase, confirmation, error).render(ui)
+                {
+                    pending = Some(msg);
+                }
+            }
+            return pending;
+        }
+
+        if let Some(view) = self.history_view.as_ref().filter(|v| v.path == note.path) {
+            let labels = view.labels();
+            let git = self.repo.as_ref().map(|_| view.git);
+            let panel = HistoryPanel::new(&labels, view.selected, &view.text, &note.body, git);
+            if let Some(msg) = panel.render(ui) {
+                pending = Some(msg);
+            }
+            return pending;
+        }
+
+        if self.preview_mode == PreviewMode::Preview {
+            let id = egui::Id::new(("note_preview", pane, &note.path));
+            return Preview::new(&note.body, &mut self.md_cache, None, id)
+                .render(ui)
+                .or(pending);
+        }
+
+        let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
+            let mut job = markdown_job(string, ui.style());
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+
+        let editor_id = editor_id(pane);
+        if focused {
+            if let Some(cursor) = self.pending_cursor.take() {
+                let ctx = ui.ctx();
+                let mut state = TextEdit::load_state(ctx, editor_id).unwrap_or_default();
+                let cursor = egui::text::CCursor::new(cursor);
+                state
+                    .cursor
+                    .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+                state.store(ctx, editor_id);
+                ctx.memory_mut(|m| m.request_focus(editor_id));
+            }
+        }
+        let before = note.body.clone();
+        // l'aperçu d'à côté suit l'éditeur du volet actif, et inversement
+        let synced = focused && self.preview_mode == PreviewMode::Split;
+        let goal = if synced {
+            self.scroll_sync.editor_goal()
+        } else {
+            None
+        };
+        // chaque note garde son défilement, dans chaque volet
+        let mut area = egui::ScrollArea::vertical().id_salt(("note_scroll", pane, &note.path));
+        let restored = if focused {
+            self.pending_scroll.take()
+        } else {
+            None
+        };
+        if let Some(offset) = restored.or(goal) {
+            area = area.vertical_scroll_offset(offset);
+        }
+        let output = area.show(ui, |ui| {
+            let mut editor = TextEdit::multiline(&mut note.body)
+                .id(editor_id)
+                .desired_rows(20)
+                .desired_width(f32::INFINITY)
+                .hint_text("Contenu…");
+            if self.preview_mode == PreviewMode::Inline {
+                editor = editor.layouter(&mut layouter);
+            }
+            let top = ui.min_rect().top();
+            let output = editor.show(ui);
+            let top = output.galley_pos.y - top;
+            let anchors = synced.then(|| preview::row_anchors(&note.body, &output.galley, top));
+            (output.response, anchors)
+        });
+        if focused {
+            self.place.scroll = output.state.offset.y;
+        }
+        let (editor, anchors) = output.inner;
+        if let Some(anchors) = anchors {
+            let followed = restored.is_none() && goal.is_some();
+            self.scroll_sync
+                .editor_shown(output.state.offset.y, anchors, followed);
+        }
+        if editor.gained_focus() && !focused {
+            pending = Some(Msg::FocusPane(pane));
+        }
+        if editor.changed() {
+            let path = note.path.clone();
+            let body = note.body.clone();
+            self.undo_stack(&path, &before).record(&before, &body, true);
+            self.save_note(f_idx, n_idx, before.len());
+        }
+        pending
+    }
+
+    /// Runs the export once the user has chosen a destination.
+    fn finish_export(&mut self, ctx: &egui::Context) {
+        let Some(target) = self.pending_export else {
+            return;
+        };
+        self.file_dialog.update(ctx);
+        let Some(out) = self.file_dialog.take_picked() else {
+            return;
+        };
+        self.pending_export = None;
+        let theme = export_theme(&ctx.style().visuals);
+        let result = match target {
+            ExportTarget::Note(f, n) => {
+                export::export_note(&self.folders[f].notes[n], &out, &theme).map(|_| out)
+            }
+            ExportTarget::NotePdf(f, n) => {
+                pdf::export_note(&self.folders[f].notes[n], &out).map(|_| out)
+            }
+            ExportTarget::Folder(f) => {
+                // les notes chiffrées restent hors de l'export
+                let mut folder = self.folders[f].clone();
+                folder.notes.retain(|n| !self.encrypted.contains(&n.path));
+                let exported = export::export_folder(&folder, &out, &theme);
+                for note in &mut folder.notes {
+                    note.body.zeroize();
                 }
+                exported
             }
+        };
+        self.export_status = Some(result.map_err(|e| e.to_string()));
+    }
+
+    /// Where the last export went, or why it failed.
+    fn show_export_status(&mut self, ctx: &egui::Context) {
+        let Some(status) = &self.export_status else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Export")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| match status {
+                Ok(path) => {
+                    ui.label(format!("Exporté vers {}", path.display()));
+                }
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, format!("L’export a échoué : {e}"));
+                }
+            });
+        if !open {
+            self.export_status = None;
         }
     }
 }
@@ -238,20 +1658,57 @@ impl eframe::App for NotesApp {
         if self.icons.is_none() {
             self.icons = Some(Icons::load(ctx));
         }
-        let icons = self.icons.as_ref().unwrap();
 
         // sélection du dossier au premier lancement
-        if self.working_dir.is_none() {
+        if self.vault.is_none() {
             if self.dir_dialog_requested {
                 self.file_dialog.pick_directory();
                 self.dir_dialog_requested = false;
             }
             self.file_dialog.update(ctx);
             if let Some(path) = self.file_dialog.take_picked() {
-                self.working_dir = Some(path);
+                self.open_vault(path);
             }
             return;
         }
+        self.reload_changed();
+        self.finish_export(ctx);
+        self.show_export_status(ctx);
+        self.auto_commit(ctx);
+        self.auto_lock(ctx);
+        let msg = self
+            .undo_shortcut(ctx)
+            .or_else(|| self.keymap_shortcut(ctx));
+        if let Some(msg) = msg.or_else(|| mouse_navigation(ctx)) {
+            self.handle(msg);
+        }
+        self.refresh_search();
+        if let Some(palette) = &self.palette {
+            let entries = self.palette_entries(palette);
+            let hint = match palette.mode {
+                PaletteMode::Commands => "Commande ou note…",
+                PaletteMode::Notes => "Note…",
+                PaletteMode::Search => "Rechercher dans les notes…",
+            };
+            if let Some(palette) = &mut self.palette {
+                let query = palette.query.clone();
+                let panel =
+                    CommandPalette::new(&mut palette.query, &mut palette.selected, &entries, hint);
+                let msg = panel.render(ctx);
+                if palette.query != query {
+                    palette.selected = 0;
+                }
+                if let Some(msg) = msg {
+                    if !matches!(msg, Msg::OpenPalette(_)) {
+                        self.palette = None;
+                    }
+                    self.handle(msg);
+                }
+            }
+        }
+        self.sync_tabs();
+        self.track_place();
+        let icons = self.icons.as_ref().unwrap();
 
         //------------------------------------------------------------------
         // Barre latérale
@@ -273,15 +1730,54 @@ impl eframe::App for NotesApp {
                 .render(ui);
             });
 
-        if let Some(msg) = pending {
+        if let Some(msg) = pending.take() {
             self.handle(msg);
         }
 
-        if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
-            let body = self.folders[f_idx].notes[n_idx].body.clone();
-            egui::SidePanel::right("preview").show(ctx, |ui| {
-                CommonMarkViewer::new().show(ui, &mut self.md_cache, &body);
-            });
+        if self.unlock_requested {
+            let first = self.vault.as_ref().is_some_and(|v| !crypt::has_key(v));
+            let mut open = true;
+            egui::Window::new("Notes chiffrées")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    let confirmation = first.then_some(&mut self.passphrase_confirmation);
+                    let error = self.unlock_error.as_deref();
+                    if let Some(msg) =
+                        UnlockPanel::new(&mut self.passphrase, confirmation, error).render(ui)
+                    {
+                        pending = Some(msg);
+                    }
+                });
+            if !open {
+                pending = Some(Msg::CancelUnlock);
+            }
+            if let Some(msg) = pending.take() {
+                self.handle(msg);
+            }
+        }
+
+        let split = self.preview_mode == PreviewMode::Split;
+        if let (Some(f_idx), Some(n_idx), true) = (self.selected, self.selected_note, split) {
+            let note = &self.folders[f_idx].notes[n_idx];
+            let body = match self.is_locked(note) {
+                true => crypt::PLACEHOLDER,
+                false => note.body.as_str(),
+            };
+            let sync = (!self.is_locked(note)).then_some(&mut self.scroll_sync);
+            let panel = egui::SidePanel::right("preview")
+                .resizable(true)
+                .default_width(self.preview_width)
+                .width_range(160.0..=ctx.screen_rect().width() * 0.7)
+                .show(ctx, |ui| {
+                    let id = egui::Id::new("side_preview");
+                    Preview::new(body, &mut self.md_cache, sync, id).render(ui)
+                });
+            self.preview_width = panel.response.rect.width();
+            if let Some(msg) = panel.inner {
+                pending = Some(msg);
+            }
         }
 
         //------------------------------------------------------------------
@@ -290,101 +1786,57 @@ impl eframe::App for NotesApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             // 1) Paramètres
             if self.show_settings {
-                ui.heading("Paramètres");
-                ui.separator();
-                ui.horizontal(|ui| {
-                    ui.label("Mode sombre");
-                    if ui.checkbox(&mut self.dark_mode, "").changed() {
-                        ctx.set_visuals(if self.dark_mode {
-                            egui::Visuals::dark()
-                        } else {
-                            egui::Visuals::light()
-                        });
-                    }
-                });
-                ui.separator();
-                ui.label(
-                    egui::RichText::new("Version 0.1 – Demo")
-                        .color(Color32::GRAY)
-                        .small(),
-                );
+                pending = SettingsPanel::new(
+                    &mut self.dark_mode,
+                    self.vault.as_ref().map(Vault::root),
+                    &mut self.settings,
+                    &self.folders,
+                    &self.templates,
+                    self.git_status.as_ref(),
+                    self.git_job.is_some(),
+                )
+                .render(ctx, ui);
                 return;
             }
 
-            // 2) Affichage / édition d’une note
-            if let (Some(f_idx), Some(n_idx)) = (self.selected, self.selected_note) {
-                {
-                    let path = self.folders[f_idx].notes[n_idx].path.clone();
-                    self.watch(&path);
-                    let note = &mut self.folders[f_idx].notes[n_idx];
-                    if let Some(rx) = &self.rx {
-                        while let Ok(evt) = rx.try_recv() {
-                            if let Ok(ev) = evt {
-                                if let Some(until) = self.ignore_fs_events_until {
-                                    if until > Instant::now() {
-                                        continue;
-                                    }
-                                    self.ignore_fs_events_until = None;
-                                }
-                                if matches!(ev.kind, EventKind::Modify(_)) {
-                                    if let Ok(txt) = fs::read_to_string(&note.path) {
-                                        note.body = txt;
-                                    }
-                                }
+            // 2) Notes ouvertes, dans un volet ou deux
+            if self.selected_note.is_some() {
+                if self.workspace.is_split() {
+                    ui.columns(2, |cols| {
+                        for (pane, ui) in cols.iter_mut().enumerate() {
+                            if let Some(msg) = self.pane_view(ui, pane) {
+                                pending = Some(msg);
                             }
                         }
-                    }
-
-                    // édition du titre (single-line)
-                    ui.add(TextEdit::singleline(&mut note.title).hint_text("Titre de la note"));
-                    ui.add_space(8.0);
-
-                    let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
-                        let mut job = markdown_job(string, ui.style());
-                        job.wrap.max_width = wrap_width;
-                        ui.fonts(|f| f.layout_job(job))
-                    };
-
-                    if ui
-                        .add(
-                            TextEdit::multiline(&mut note.body)
-                                .desired_rows(20)
-                                .layouter(&mut layouter)
-                                .hint_text("Contenu…"),
-                        )
-                        .changed()
-                    {
-                        let _ = fs::write(&note.path, &note.body);
-                        self.ignore_fs_events_until =
-                            Some(Instant::now() + Duration::from_millis(500));
-                        let parser = Parser::new(&note.body);
-                        let mut html = String::new();
-                        html::push_html(&mut html, parser);
-                        let _ = fs::write(note.path.with_extension("html"), html);
-                    }
+                    });
+                } else {
+                    pending = self.pane_view(ui, 0);
                 }
-
                 return;
             }
+            let pane = self.workspace.focused;
+            if !self.workspace.focused().tabs.is_empty() {
+                let titles = self.tab_titles(pane);
+                let active = self.workspace.focused().active;
+                pending = TabBar::new(&titles, active, pane, false, None).render(ui);
+                ui.separator();
+            }
 
             // 3) Liste des notes d’un dossier
             if let Some(f_idx) = self.selected {
-                let folder = &mut self.folders[f_idx];
-                ui.heading(&folder.name);
-                ui.add_space(8.0);
-
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for (idx, note) in folder.notes.iter().enumerate() {
-                        if ui
-                            .selectable_label(false, &note.title)
-                            .on_hover_text(&note.body)
-                            .clicked()
-                        {
-                            self.selected_note = Some(idx);
-                        }
-                        ui.add_space(4.0);
-                    }
-                });
+                if journal::is_journal(&self.settings, &self.folders[f_idx]) {
+                    pending = self.calendar(ui, f_idx);
+                    ui.separator();
+                }
+                let encrypted = self
+                    .settings
+                    .encrypted_folders
+                    .contains(&self.folders[f_idx].name);
+                let panel =
+                    NotesPanel::new(&self.folders, self.selected, &self.templates, encrypted);
+                if let Some(msg) = panel.render(ui) {
+                    pending = Some(msg);
+                }
                 return;
             }
 
@@ -392,26 +1844,22 @@ impl eframe::App for NotesApp {
             ui.heading("Bienvenue dans Notes App !");
             ui.label("Sélectionne un dossier ou crée-en un nouveau avec le bouton +.");
         });
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn heading_layout_has_no_extra_newline() {
-        let style = egui::Style::default();
-        let text = "# les";
-        let job = markdown_job(text, &style);
-        assert!(!job.text.contains('\n'));
+        if let Some(msg) = pending {
+            self.handle(msg);
+        }
     }
 
-    #[test]
-    fn heading_layout_preserves_text_order() {
-        let style = egui::Style::default();
-        let text = "# les";
-        let job = markdown_job(text, &style);
-        assert_eq!(job.text, "les");
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save_undo();
+        if let Some(vault) = &self.vault {
+            eframe::set_value(storage, VAULT_KEY, &vault.root());
+            eframe::set_value(storage, WORKSPACE_KEY, &(vault.root(), &self.workspace));
+        }
+        eframe::set_value(
+            storage,
+            PREVIEW_KEY,
+            &(self.preview_mode, self.preview_width),
+        );
     }
 }
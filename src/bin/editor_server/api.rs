@@ -0,0 +1,322 @@
+use crate::auth::Access;
+use notes_app::crypt;
+use notes_app::models::Note;
+use notes_app::render;
+use notes_app::settings::Settings;
+use notes_app::url::percent_decode;
+use notes_app::vault::Vault;
+use pulldown_cmark::{CodeBlockKind, Event, Tag};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Cursor, ErrorKind, Read};
+use tiny_http::{Header, Method, Request, Response};
+
+/// Request bodies larger than this are rejected.
+const MAX_BODY: u64 = 8 * 1024 * 1024;
+
+pub type JsonResponse = Response<Cursor<Vec<u8>>>;
+
+#[derive(Deserialize)]
+struct NewNote {
+    folder: String,
+    title: String,
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct NoteBody {
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct Markdown {
+    markdown: String,
+}
+
+/// Routes a `/api/...` request against the vault.
+///
+/// * `GET /api/folders`
+/// * `GET|PUT|DELETE /api/notes/{path}` (`GET` also returns the body as HTML
+///   for the rich editor, and whether it can edit it without losing anything)
+/// * `POST /api/notes` with `{"folder", "title", "body"}`
+/// * `GET /api/search?q=`
+/// * `POST /api/render` with `{"markdown"}`: `{"html"}`, rendered like the notes
+/// * `GET /api/session`: `{"read_only": bool, "collab_port": u16}`, the port
+///   of the WebSocket used for collaborative editing
+///
+/// Read-only access only allows `GET` (and rendering, which changes nothing).
+pub fn handle(
+    vault: &Vault,
+    access: Access,
+    collab_port: u16,
+    request: &mut Request,
+) -> JsonResponse {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+
+    let result = match (&method, path) {
+        (Method::Get, "/api/session") => Ok((
+            200,
+            json!({ "read_only": access == Access::ReadOnly, "collab_port": collab_port }),
+        )),
+        (Method::Post, "/api/render") => read_json::<Markdown>(request)
+            .map(|m| (200, json!({ "html": render::to_html(&m.markdown) }))),
+        (m, _) if *m != Method::Get && access == Access::ReadOnly => Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            "read-only access",
+        )),
+        (Method::Get, "/api/folders") => folders(vault),
+        (Method::Get, "/api/search") => search(vault, &query_param(query, "q").unwrap_or_default()),
+        (Method::Post, "/api/notes") => read_json::<NewNote>(request).and_then(|new| {
+            if encrypted_folder(vault, &new.folder)? {
+                return Err(crypt::locked());
+            }
+            let note = vault.create_note(&new.folder, &new.title, &new.body)?;
+            Ok((201, note_json(vault, &note)))
+        }),
+        (_, p) if p.starts_with("/api/notes/") => {
+            let rel = percent_decode(&p["/api/notes/".len()..]);
+            match method {
+                Method::Get => readable(vault, &rel).map(|n| {
+                    let mut body = note_json(vault, &n);
+                    body["html"] = render::to_html(&n.body).into();
+                    body["rich"] = rich_editable(&n.body).into();
+                    (200, body)
+                }),
+                Method::Put => read_json::<NoteBody>(request).and_then(|b| {
+                    let note = write(vault, &rel, &b.body)?;
+                    Ok((200, note_json(vault, &note)))
+                }),
+                Method::Delete => vault
+                    .delete_note(&rel)
+                    .map(|_| (200, json!({ "deleted": rel }))),
+                _ => Err(io::Error::new(ErrorKind::Unsupported, "method not allowed")),
+            }
+        }
+        _ => Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("no route for {path}"),
+        )),
+    };
+
+    match result {
+        Ok((status, body)) => json_response(status, &body),
+        Err(e) => json_response(status_of(&e), &json!({ "error": e.to_string() })),
+    }
+}
+
+pub fn json_response(status: u16, body: &Value) -> JsonResponse {
+    Response::from_data(body.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap())
+}
+
+fn status_of(e: &io::Error) -> u16 {
+    match e.kind() {
+        ErrorKind::NotFound => 404,
+        ErrorKind::InvalidInput | ErrorKind::InvalidData => 400,
+        ErrorKind::PermissionDenied => 403,
+        ErrorKind::AlreadyExists => 409,
+        ErrorKind::Unsupported => 405,
+        _ => 500,
+    }
+}
+
+fn folders(vault: &Vault) -> io::Result<(u16, Value)> {
+    let (folders, skipped) = vault.load()?;
+    let folders: Vec<Value> = folders
+        .iter()
+        .map(|f| {
+            json!({
+                "name": f.name,
+                "path": vault.relative(&f.path),
+                "color": f.color.hex(),
+                "notes": f.notes.iter().map(|n| json!({
+                    "path": vault.relative(&n.path),
+                    "title": n.title,
+                })).collect::<Vec<_>>(),
+                // notes illisibles (pas en UTF-8…), listées à part
+                "skipped": skipped
+                    .iter()
+                    .filter(|(path, _)| path.parent() == Some(f.path.as_path()))
+                    .map(|(path, e)| json!({
+                        "path": vault.relative(path),
+                        "error": e.to_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    Ok((200, Value::Array(folders)))
+}
+
+fn search(vault: &Vault, q: &str) -> io::Result<(u16, Value)> {
+    let hits: Vec<Value> = vault
+        .search(q)?
+        .iter()
+        .map(|h| {
+            json!({
+                "path": vault.relative(&h.note.path),
+                "title": h.note.title,
+                "snippet": h.snippet,
+            })
+        })
+        .collect();
+    Ok((200, Value::Array(hits)))
+}
+
+fn note_json(vault: &Vault, note: &Note) -> Value {
+    json!({
+        "path": vault.relative(&note.path),
+        "title": note.title,
+        "body": note.body,
+    })
+}
+
+/// `GET /render/{path}`: the note as an HTML fragment.
+pub fn render_note(vault: &Vault, path: &str) -> JsonResponse {
+    match readable(vault, &percent_decode(path)) {
+        Ok(note) => Response::from_data(render::to_html(&note.body).into_bytes())
+            .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap()),
+        Err(e) => json_response(status_of(&e), &json!({ "error": e.to_string() })),
+    }
+}
+
+/// The note `rel`, refused when it is encrypted: the server never has the
+/// key, and its armor is not worth editing.
+fn readable(vault: &Vault, rel: &str) -> io::Result<Note> {
+    let note = vault.read_note(rel)?;
+    if crypt::is_encrypted(&note.body) {
+        return Err(crypt::locked());
+    }
+    Ok(note)
+}
+
+/// Whether the new notes of `folder` are encrypted: the server cannot
+/// create them, it never has the key.
+fn encrypted_folder(vault: &Vault, folder: &str) -> io::Result<bool> {
+    Ok(Settings::load(vault)?.encrypted_folders.contains(folder))
+}
+
+/// `PUT` of the note `rel`: refused for an encrypted note, and for a new one
+/// in an encrypted folder.
+fn write(vault: &Vault, rel: &str, body: &str) -> io::Result<Note> {
+    match vault.read_note(rel) {
+        Ok(note) if crypt::is_encrypted(&note.body) => return Err(crypt::locked()),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let folder = rel.rsplit_once('/').map_or("", |(folder, _)| folder);
+            if encrypted_folder(vault, folder)? {
+                return Err(crypt::locked());
+            }
+        }
+        _ => {}
+    }
+    vault.write_note(rel, body)
+}
+
+/// False when the note uses Markdown that the rich editor (`assets/rich.js`)
+/// cannot represent (tables, task lists, raw HTML, footnotes, code block
+/// languages…): the web editor then edits the source instead of rewriting it.
+pub fn rich_editable(md: &str) -> bool {
+    // l'éditeur riche réécrirait l'indentation des blocs imbriqués : pas de
+    // liste ni de citation dans un autre bloc
+    let mut nested = 0;
+    for ev in render::parser(md) {
+        match ev {
+            Event::Start(Tag::List(_) | Tag::BlockQuote) => {
+                if nested > 0 {
+                    return false;
+                }
+                nested += 1;
+            }
+            Event::End(Tag::List(_) | Tag::BlockQuote) => nested -= 1,
+            Event::Start(Tag::Heading(..) | Tag::CodeBlock(_)) if nested > 0 => return false,
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if !lang.is_empty() => {
+                return false
+            }
+            Event::Start(Tag::Link(_, _, title) | Tag::Image(_, _, title)) if !title.is_empty() => {
+                return false
+            }
+            Event::Start(Tag::Table(_) | Tag::FootnoteDefinition(_))
+            | Event::Html(_)
+            | Event::FootnoteReference(_)
+            | Event::TaskListMarker(_)
+            | Event::HardBreak => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> io::Result<T> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "request body too large",
+        ));
+    }
+    serde_json::from_slice(&body).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))
+}
+
+pub fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| percent_decode(&v.replace('+', " ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_query_parameters() {
+        assert_eq!(
+            query_param("x=1&q=id%C3%A9e+projet", "q").as_deref(),
+            Some("idée projet")
+        );
+        assert_eq!(query_param("x=1", "q"), None);
+        assert_eq!(percent_decode("Travail/100%"), "Travail/100%");
+    }
+
+    #[test]
+    fn refuses_new_notes_in_encrypted_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        std::fs::create_dir_all(dir.path().join("Secret")).unwrap();
+        let mut settings = Settings::default();
+        settings.encrypted_folders.insert("Secret".into());
+        settings.save(&vault).unwrap();
+
+        assert!(encrypted_folder(&vault, "Secret").unwrap());
+        let err = write(&vault, "Secret/Codes.md", "wifi : hunter2\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(!dir.path().join("Secret/Codes.md").exists());
+
+        // une note en clair d'avant le chiffrement du dossier reste modifiable
+        std::fs::write(dir.path().join("Secret/Ancienne.md"), "").unwrap();
+        assert!(write(&vault, "Secret/Ancienne.md", "modifiée\n").is_ok());
+    }
+
+    #[test]
+    fn tables_are_not_edited_rich() {
+        assert!(rich_editable(
+            "# Titre\n\n- **a**\n- [lien](x)\n\n```\ncode\n```\n"
+        ));
+        assert!(!rich_editable("| a | b |\n|---|---|\n| 1 | 2 |\n"));
+        assert!(!rich_editable("```rust\nfn main() {}\n```\n"));
+        assert!(!rich_editable("<div>html</div>\n"));
+        assert!(!rich_editable("- a\n    - b\n"));
+        assert!(!rich_editable("- [ ] à faire\n"));
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: editor_server [options] [vault]
+
+Serves the web editor for the notes in [vault] (default: current directory).
+
+Options:
+      --host <addr>   address to bind (default 127.0.0.1, 0.0.0.0 for the LAN)
+  -p, --port <port>   port to listen on (default 8000); if it is busy, a free
+                      port is picked unless --strict-port is given
+      --strict-port   fail when the port is busy
+      --ws-port <port>
+                      port of the collaborative editing WebSocket, with the
+                      same certificate as HTTPS (default: the port after the
+                      HTTP one; a busy port is handled like --port)
+      --cert <file>   PEM certificate: serve over HTTPS (requires --key)
+      --key <file>    PEM private key of the certificate
+      --print-token   print the access token of the vault and exit
+      --read-only-token <name>
+                      create a read-only token for sharing, print it and exit
+  -h, --help          print this help
+
+Environment variables EDITOR_HOST, EDITOR_PORT, EDITOR_WS_PORT, NOTES_VAULT,
+EDITOR_TLS_CERT and EDITOR_TLS_KEY provide defaults for the matching options.";
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub vault: PathBuf,
+    pub host: String,
+    pub port: u16,
+    pub strict_port: bool,
+    /// `None`: the port after the HTTP one.
+    pub ws_port: Option<u16>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub print_token: bool,
+    pub read_only_token: Option<String>,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            vault: PathBuf::from("."),
+            host: "127.0.0.1".into(),
+            port: 8000,
+            strict_port: false,
+            ws_port: None,
+            cert: None,
+            key: None,
+            print_token: false,
+            read_only_token: None,
+            help: false,
+        }
+    }
+}
+
+/// Parses the arguments following the program name; `env` looks up the
+/// environment variables, which command-line options override.
+pub fn parse(
+    args: impl IntoIterator<Item = String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    if let Some(host) = env("EDITOR_HOST") {
+        parsed.host = host;
+    }
+    if let Some(port) = env("EDITOR_PORT") {
+        parsed.port = port
+            .parse()
+            .map_err(|_| format!("invalid EDITOR_PORT value: {port}"))?;
+    }
+    if let Some(port) = env("EDITOR_WS_PORT") {
+        parsed.ws_port = Some(
+            port.parse()
+                .map_err(|_| format!("invalid EDITOR_WS_PORT value: {port}"))?,
+        );
+    }
+    if let Some(vault) = env("NOTES_VAULT") {
+        parsed.vault = vault.into();
+    }
+    parsed.cert = env("EDITOR_TLS_CERT").map(PathBuf::from);
+    parsed.key = env("EDITOR_TLS_KEY").map(PathBuf::from);
+
+    let mut vault = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} expects a value"));
+        match arg.as_str() {
+            "--host" => parsed.host = value(&arg)?,
+            "-p" | "--port" => {
+                let port = value(&arg)?;
+                parsed.port = port
+                    .parse()
+                    .map_err(|_| format!("invalid --port value: {port}"))?;
+            }
+            "--strict-port" => parsed.strict_port = true,
+            "--ws-port" => {
+                let port = value(&arg)?;
+                parsed.ws_port = Some(
+                    port.parse()
+                        .map_err(|_| format!("invalid --ws-port value: {port}"))?,
+                );
+            }
+            "--cert" => parsed.cert = Some(value(&arg)?.into()),
+            "--key" => parsed.key = Some(value(&arg)?.into()),
+            "--print-token" => parsed.print_token = true,
+            "--read-only-token" => parsed.read_only_token = Some(value(&arg)?),
+            "-h" | "--help" => parsed.help = true,
+            s if s.starts_with('-') && s.len() > 1 => return Err(format!("unknown option: {s}")),
+            _ if vault.is_some() => return Err(format!("unexpected argument: {arg}")),
+            _ => vault = Some(PathBuf::from(arg)),
+        }
+    }
+    if let Some(vault) = vault {
+        parsed.vault = vault;
+    }
+
+    if parsed.cert.is_some() != parsed.key.is_some() {
+        return Err("--cert and --key must be given together".into());
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str, env: &[(&str, &str)]) -> Result<Args, String> {
+        parse(s.split_whitespace().map(String::from), |name| {
+            env.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn options_override_environment() {
+        let env = [
+            ("EDITOR_HOST", "0.0.0.0"),
+            ("EDITOR_PORT", "9000"),
+            ("NOTES_VAULT", "/srv"),
+        ];
+        let a = args("", &env).unwrap();
+        assert_eq!((a.host.as_str(), a.port), ("0.0.0.0", 9000));
+        assert_eq!(a.vault, PathBuf::from("/srv"));
+
+        let a = args("~/Notes --port 8443 --cert c.pem --key k.pem", &env).unwrap();
+        assert_eq!(a.port, 8443);
+        assert_eq!(a.vault, PathBuf::from("~/Notes"));
+        assert_eq!(a.cert, Some(PathBuf::from("c.pem")));
+
+        assert_eq!(a.ws_port, None);
+        let a = args("--ws-port 8444", &[("EDITOR_WS_PORT", "9001")]).unwrap();
+        assert_eq!(a.ws_port, Some(8444));
+        let a = args("", &[("EDITOR_WS_PORT", "9001")]).unwrap();
+        assert_eq!(a.ws_port, Some(9001));
+
+        let a = args("--read-only-token alice", &[]).unwrap();
+        assert_eq!(a.read_only_token.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        assert!(args("a b", &[]).is_err());
+        assert!(args("--port web", &[]).is_err());
+        assert!(args("--ws-port", &[]).is_err());
+        assert!(args("--cert c.pem", &[]).is_err());
+        assert!(args("", &[("EDITOR_PORT", "x")]).is_err());
+        assert!(args("--frobnicate", &[]).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::Cursor;
+use tiny_http::{Header, Request, Response};
+
+/// A file compiled into the binary, served under `/static/`.
+struct Asset {
+    name: &'static str,
+    bytes: &'static [u8],
+    content_type: &'static str,
+}
+
+const ASSETS: &[Asset] = &[
+    Asset {
+        name: "editor.css",
+        bytes: include_bytes!("../../../assets/editor.css"),
+        content_type: "text/css; charset=utf-8",
+    },
+    Asset {
+        name: "editor.js",
+        bytes: include_bytes!("../../../assets/editor.js"),
+        content_type: "text/javascript; charset=utf-8",
+    },
+    Asset {
+        name: "rich.js",
+        bytes: include_bytes!("../../../assets/rich.js"),
+        content_type: "text/javascript; charset=utf-8",
+    },
+];
+
+pub const INDEX: &str = include_str!("../../../assets/editor.html");
+
+/// Serves `/static/{name}`. Browsers revalidate with the ETag on every load,
+/// so a rebuilt server never leaves a stale editor in the cache.
+pub fn serve(name: &str, request: &Request) -> Response<Cursor<Vec<u8>>> {
+    let Some(asset) = ASSETS.iter().find(|a| a.name == name) else {
+        return Response::from_data(Vec::new()).with_status_code(404);
+    };
+    let etag = format!("\"{:016x}\"", fnv1a(asset.bytes));
+    let cached = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("If-None-Match") && h.value.as_str() == etag);
+
+    let response = if cached {
+        Response::from_data(Vec::new()).with_status_code(304)
+    } else {
+        Response::from_data(asset.bytes.to_vec())
+            .with_header(header("Content-Type", asset.content_type))
+    };
+    response
+        .with_header(header("Cache-Control", "no-cache"))
+        .with_header(header("ETag", &etag))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name, value).unwrap()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_page_only_loads_embedded_assets() {
+        let mut refs = 0;
+        for part in INDEX.split("\"/static/").skip(1) {
+            let name = &part[..part.find('"').unwrap()];
+            assert!(
+                ASSETS.iter().any(|a| a.name == name),
+                "/static/{name} is not embedded"
+            );
+            refs += 1;
+        }
+        assert_eq!(refs, ASSETS.len());
+        // rien ne vient d'ailleurs que du serveur
+        assert!(!INDEX.contains("://"));
+    }
+}
@@ -0,0 +1,278 @@
+use notes_app::vault::Vault;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tiny_http::{Header, Request, Response};
+
+/// Sessions opened through the login page last this long.
+const SESSION_LIFETIME: Duration = Duration::from_secs(30 * 24 * 3600);
+const COOKIE: &str = "notes_session";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    ReadWrite,
+    ReadOnly,
+}
+
+/// Stored in `.notes/editor_server.json` inside the vault.
+#[derive(Serialize, Deserialize)]
+struct Config {
+    /// Full access, for the owner of the vault.
+    secret: String,
+    /// Tokens handed out for sharing, by name.
+    #[serde(default)]
+    read_only_tokens: BTreeMap<String, String>,
+}
+
+struct Session {
+    access: Access,
+    expires: Instant,
+}
+
+/// Tokens of the vault and the sessions opened with them. Sessions live in
+/// memory only: restarting the server logs everybody out.
+pub struct Auth {
+    path: PathBuf,
+    config: Config,
+    /// Modification time of the file when `config` was read.
+    modified: Option<SystemTime>,
+    sessions: HashMap<String, Session>,
+    secure: bool,
+}
+
+impl Auth {
+    /// Loads the vault tokens, generating the secret on first use. The flag
+    /// is true when the secret has just been created.
+    pub fn load(vault: &Vault, secure: bool) -> io::Result<(Self, bool)> {
+        let path = vault.config_dir().join("editor_server.json");
+        let (config, created) = match fs::read_to_string(&path) {
+            Ok(text) => (
+                serde_json::from_str(&text)
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?,
+                false,
+            ),
+            Err(e) if e.kind() == ErrorKind::NotFound => (
+                Config {
+                    secret: random_token()?,
+                    read_only_tokens: BTreeMap::new(),
+                },
+                true,
+            ),
+            Err(e) => return Err(e),
+        };
+        let mut auth = Self {
+            path,
+            config,
+            modified: None,
+            sessions: HashMap::new(),
+            secure,
+        };
+        if created {
+            auth.save()?;
+        }
+        auth.modified = modified(&auth.path);
+        Ok((auth, created))
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.config.secret
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Creates (or replaces) the read-only token called `name`.
+    pub fn add_read_only_token(&mut self, name: &str) -> io::Result<String> {
+        let token = random_token()?;
+        self.config
+            .read_only_tokens
+            .insert(name.to_owned(), token.clone());
+        self.save()?;
+        Ok(token)
+    }
+
+    /// Access granted by a bearer token or a session cookie.
+    pub fn authenticate(&mut self, request: &Request) -> Option<Access> {
+        self.check(header(request, "Authorization"), header(request, "Cookie"))
+    }
+
+    /// Same as [`Auth::authenticate`], from the raw header values.
+    pub fn check(&mut self, authorization: Option<&str>, cookies: Option<&str>) -> Option<Access> {
+        self.reload();
+        if let Some(token) = authorization.and_then(|v| v.strip_prefix("Bearer ")) {
+            return self.check_token(token.trim());
+        }
+        let id = cookie(cookies?, COOKIE)?;
+        let now = Instant::now();
+        self.sessions.retain(|_, s| s.expires > now);
+        self.sessions.get(&id).map(|s| s.access)
+    }
+
+    /// Opens a session for `token`; returns the `Set-Cookie` header value.
+    pub fn login(&mut self, token: &str) -> io::Result<Option<String>> {
+        self.reload();
+        let Some(access) = self.check_token(token.trim()) else {
+            return Ok(None);
+        };
+        let id = random_token()?;
+        let expires = Instant::now() + SESSION_LIFETIME;
+        self.sessions
+            .insert(id.clone(), Session { access, expires });
+        Ok(Some(format!(
+            "{COOKIE}={id}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+            SESSION_LIFETIME.as_secs(),
+            if self.secure { "; Secure" } else { "" }
+        )))
+    }
+
+    /// Closes the session of `request`; returns the header clearing the cookie.
+    pub fn logout(&mut self, request: &Request) -> String {
+        if let Some(id) = header(request, "Cookie").and_then(|c| cookie(c, COOKIE)) {
+            self.sessions.remove(&id);
+        }
+        format!("{COOKIE}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0")
+    }
+
+    fn check_token(&self, token: &str) -> Option<Access> {
+        if constant_time_eq(token, &self.config.secret) {
+            return Some(Access::ReadWrite);
+        }
+        self.config
+            .read_only_tokens
+            .values()
+            .any(|t| constant_time_eq(token, t))
+            .then_some(Access::ReadOnly)
+    }
+
+    /// Picks up tokens added with `--read-only-token` while the server runs.
+    fn reload(&mut self) {
+        let now = modified(&self.path);
+        if now == self.modified {
+            return;
+        }
+        match fs::read_to_string(&self.path).map(|t| serde_json::from_str(&t)) {
+            Ok(Ok(config)) => {
+                self.config = config;
+                self.modified = now;
+            }
+            Ok(Err(e)) => eprintln!("Ignoring invalid {}: {e}", self.path.display()),
+            Err(e) => eprintln!("Cannot read {}: {e}", self.path.display()),
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(self.path.parent().unwrap())?;
+        let json = serde_json::to_string_pretty(&self.config)?;
+        let mut file = fs::OpenOptions::new();
+        file.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut file, 0o600);
+        io::Write::write_all(&mut file.open(&self.path)?, json.as_bytes())
+    }
+}
+
+/// The login form; `failed` adds an error message.
+pub fn login_page(failed: bool) -> Response<Cursor<Vec<u8>>> {
+    let error = if failed {
+        "<p class=\"error\">Jeton invalide.</p>"
+    } else {
+        ""
+    };
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="fr">
+<head>
+<meta charset="UTF-8" />
+<meta name="viewport" content="width=device-width, initial-scale=1" />
+<title>Connexion</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
+  background: #fafafa; display: flex; justify-content: center; padding-top: 15vh; }}
+form {{ display: flex; flex-direction: column; gap: 0.6rem; width: 20rem; }}
+input {{ padding: 0.4rem; font-size: 1rem; }}
+.error {{ color: #c0392b; }}
+</style>
+</head>
+<body>
+<form method="post" action="/login">
+<h1>Notes</h1>
+<label for="token">Jeton d'accès</label>
+<input id="token" name="token" type="password" autocomplete="current-password" autofocus required />
+{error}
+<button type="submit">Se connecter</button>
+</form>
+</body>
+</html>
+"#
+    );
+    Response::from_data(html.into_bytes())
+        .with_status_code(if failed { 401 } else { 200 })
+        .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn cookie(cookies: &str, name: &str) -> Option<String> {
+    cookies
+        .split(';')
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v.to_owned())
+}
+
+/// 256 random bits, hex-encoded.
+fn random_token() -> io::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compares without stopping at the first difference, so the response time
+/// does not tell how much of a guessed token was right.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_grant_their_access() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        let (mut auth, created) = Auth::load(&vault, false).unwrap();
+        assert!(created);
+        let secret = auth.secret().to_owned();
+        let shared = auth.add_read_only_token("lecture").unwrap();
+
+        let (auth2, created) = Auth::load(&vault, false).unwrap();
+        assert!(!created);
+        assert_eq!(auth2.check_token(&secret), Some(Access::ReadWrite));
+        assert_eq!(auth2.check_token(&shared), Some(Access::ReadOnly));
+        assert_eq!(auth2.check_token(""), None);
+        assert_eq!(auth2.check_token(&secret[1..]), None);
+
+        let cookie = auth.login(&shared).unwrap().unwrap();
+        assert!(cookie.starts_with("notes_session=") && cookie.contains("HttpOnly"));
+        assert!(auth.login("nope").unwrap().is_none());
+    }
+}
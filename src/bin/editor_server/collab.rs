@@ -0,0 +1,473 @@
+//! Collaborative editing: every open note is a yrs document whose text is
+//! the Markdown body. Clients send the whole text they have after editing
+//! a version they received; the server diffs it against that version,
+//! replays the difference on a replica and merges the replica into the
+//! shared document, so concurrent edits from other clients are kept. The
+//! file on disk is one more replica: it is written after every merge, and
+//! changes made by other programs (the desktop app) are merged back.
+
+use crate::api::rich_editable;
+use notes_app::crypt;
+use notes_app::render;
+use notes_app::vault::Vault;
+use serde_json::{json, Value};
+use similar::{capture_diff_slices_deadline, Algorithm, DiffOp};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use yrs::updates::decoder::Decode;
+use yrs::{ClientID, Doc, GetString, ReadTxn, StateVector, Text, TextRef, Transact, Update};
+
+/// Versions kept per client; older ones can no longer be edited against.
+const MAX_VERSIONS: usize = 64;
+
+/// yrs client id of the edits of the document itself: its first text and the
+/// changes merged from the file. Connections use their own id, from 1 up.
+const FILE_CLIENT: u64 = 0;
+
+/// Opened notes and the clients editing them.
+pub struct Hub {
+    vault: Vault,
+    docs: Mutex<HashMap<String, Document>>,
+}
+
+/// A state of a document, as seen by a client or written to disk.
+#[derive(Clone)]
+struct Snapshot {
+    text: String,
+    /// The whole yrs state, to rebuild a replica from.
+    state: Vec<u8>,
+}
+
+struct Client {
+    tx: Sender<String>,
+    /// Versions sent to the client, oldest first.
+    versions: VecDeque<(u64, Snapshot)>,
+    next: u64,
+}
+
+struct Document {
+    rel: String,
+    path: PathBuf,
+    doc: Doc,
+    body: TextRef,
+    /// What the file contains, as far as we know.
+    disk: Snapshot,
+    disk_modified: Option<SystemTime>,
+    /// The note was encrypted from the desktop app: its file is left alone.
+    encrypted: bool,
+    clients: HashMap<u64, Client>,
+}
+
+impl Hub {
+    pub fn new(vault: Vault) -> Self {
+        Self {
+            vault,
+            docs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribes `client` to the note `rel`; it gets a `sync` message with
+    /// the current text right away.
+    pub fn join(&self, rel: &str, client: u64, tx: Sender<String>) -> io::Result<()> {
+        let mut docs = self.docs.lock().unwrap();
+        if !docs.contains_key(rel) {
+            let note = self.vault.read_note(rel)?;
+            if crypt::is_encrypted(&note.body) {
+                return Err(crypt::locked());
+            }
+            docs.insert(rel.to_owned(), Document::new(rel, note.path, &note.body));
+        }
+        let doc = docs.get_mut(rel).unwrap();
+        doc.reconcile_disk();
+        doc.clients.insert(
+            client,
+            Client {
+                tx,
+                versions: VecDeque::new(),
+                next: 1,
+            },
+        );
+        let snapshot = doc.snapshot();
+        let message = sync_message(rel, &snapshot, doc.clients.len());
+        doc.clients
+            .get_mut(&client)
+            .unwrap()
+            .send_version(snapshot, message);
+        doc.broadcast_editors();
+        Ok(())
+    }
+
+    pub fn leave(&self, rel: &str, client: u64) {
+        let mut docs = self.docs.lock().unwrap();
+        if let Some(doc) = docs.get_mut(rel) {
+            doc.clients.remove(&client);
+            if doc.clients.is_empty() {
+                docs.remove(rel);
+            } else {
+                doc.broadcast_editors();
+            }
+        }
+    }
+
+    /// Merges `text`, written by `client` on top of version `base`.
+    pub fn edit(&self, rel: &str, client: u64, base: u64, text: &str) -> io::Result<()> {
+        let mut docs = self.docs.lock().unwrap();
+        let doc = docs
+            .get_mut(rel)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{rel} is not open")))?;
+        doc.reconcile_disk();
+        let c = doc
+            .clients
+            .get_mut(&client)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{rel} is not open")))?;
+        // le client ne revient jamais en arrière : les versions antérieures sont inutiles
+        while c.versions.len() > 1 && c.versions[0].0 < base {
+            c.versions.pop_front();
+        }
+        let snapshot = c
+            .versions
+            .iter()
+            .find(|(v, _)| *v == base)
+            .map(|(_, s)| s.clone())
+            .ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidInput, format!("unknown version {base}"))
+            })?;
+
+        let edited = doc.merge(client, &snapshot, text)?;
+        // l'accusé de réception porte la version que le client a sous les yeux
+        let c = doc.clients.get_mut(&client).unwrap();
+        c.send_version(edited, json!({ "type": "ack", "path": rel }));
+        doc.changed();
+        Ok(())
+    }
+
+    /// Merges changes other programs made to the files of open notes.
+    pub fn reconcile(&self) {
+        for doc in self.docs.lock().unwrap().values_mut() {
+            doc.reconcile_disk();
+        }
+    }
+}
+
+impl Client {
+    /// Records `snapshot` as the next version and sends `message` with that
+    /// version number.
+    fn send_version(&mut self, snapshot: Snapshot, mut message: Value) {
+        message["version"] = self.next.into();
+        self.versions.push_back((self.next, snapshot));
+        if self.versions.len() > MAX_VERSIONS {
+            self.versions.pop_front();
+        }
+        self.next += 1;
+        let _ = self.tx.send(message.to_string());
+    }
+}
+
+impl Document {
+    fn new(rel: &str, path: PathBuf, body: &str) -> Self {
+        let doc = Doc::with_client_id(FILE_CLIENT);
+        let text = doc.get_or_insert_text("body");
+        text.insert(&mut doc.transact_mut(), 0, body);
+        let mut document = Self {
+            rel: rel.to_owned(),
+            disk_modified: modified(&path),
+            path,
+            body: text,
+            disk: Snapshot {
+                text: String::new(),
+                state: Vec::new(),
+            },
+            encrypted: false,
+            doc,
+            clients: HashMap::new(),
+        };
+        document.disk = document.snapshot();
+        document
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let txn = self.doc.transact();
+        Snapshot {
+            text: self.body.get_string(&txn),
+            state: txn.encode_state_as_update_v1(&StateVector::default()),
+        }
+    }
+
+    /// Applies the difference between `base` and `text` to a replica of
+    /// `base` edited as `client`, merges the replica into the document and
+    /// returns its state.
+    fn merge(&mut self, client: u64, base: &Snapshot, text: &str) -> io::Result<Snapshot> {
+        let replica = Doc::with_client_id(client);
+        let body = replica.get_or_insert_text("body");
+        let mut txn = replica.transact_mut();
+        let update = Update::decode_v1(&base.state).map_err(invalid)?;
+        txn.apply_update(update).map_err(invalid)?;
+        let before = txn.state_vector();
+        // le client reprend ses numéros d'opération là où `base` les laisse :
+        // `base` doit donc contenir toutes ses éditions précédentes
+        let id = ClientID::new(client);
+        if before.get(&id) != self.doc.transact().state_vector().get(&id) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "version older than the last edit",
+            ));
+        }
+        for (at, removed, inserted) in text_edits(&base.text, text) {
+            if removed > 0 {
+                body.remove_range(&mut txn, at as u32, removed as u32);
+            }
+            if !inserted.is_empty() {
+                body.insert(&mut txn, at as u32, inserted);
+            }
+        }
+        let diff = txn.encode_diff_v1(&before);
+        let edited = Snapshot {
+            text: body.get_string(&txn),
+            state: txn.encode_state_as_update_v1(&StateVector::default()),
+        };
+        drop(txn);
+
+        let update = Update::decode_v1(&diff).map_err(invalid)?;
+        self.doc
+            .transact_mut()
+            .apply_update(update)
+            .map_err(invalid)?;
+        Ok(edited)
+    }
+
+    /// Writes the merged text and sends it to every client.
+    fn changed(&mut self) {
+        let snapshot = self.snapshot();
+        if snapshot.text != self.disk.text && !self.encrypted {
+            match fs::write(&self.path, &snapshot.text) {
+                Ok(()) => {
+                    self.disk = snapshot.clone();
+                    self.disk_modified = modified(&self.path);
+                }
+                Err(e) => eprintln!("Cannot write {}: {e}", self.path.display()),
+            }
+        }
+        let editors = self.clients.len();
+        let message = sync_message(&self.rel, &snapshot, editors);
+        for client in self.clients.values_mut() {
+            client.send_version(snapshot.clone(), message.clone());
+        }
+    }
+
+    /// If the file changed since we last wrote it, merges the change as an
+    /// edit made on top of what we wrote.
+    fn reconcile_disk(&mut self) {
+        let now = modified(&self.path);
+        if now == self.disk_modified {
+            return;
+        }
+        self.disk_modified = now;
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) if crypt::is_encrypted(&text) => {
+                eprintln!("{} was encrypted, its edits are no longer saved", self.rel);
+                self.encrypted = true;
+                return;
+            }
+            Ok(text) if text != self.disk.text => text,
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("Cannot read {}: {e}", self.path.display());
+                return;
+            }
+        };
+        let base = self.disk.clone();
+        match self.merge(FILE_CLIENT, &base, &text) {
+            Ok(edited) => {
+                self.disk = edited;
+                self.changed();
+            }
+            Err(e) => eprintln!("Cannot merge {}: {e}", self.path.display()),
+        }
+    }
+
+    fn broadcast_editors(&self) {
+        let message = json!({ "type": "editors", "path": self.rel, "editors": self.clients.len() });
+        let message = message.to_string();
+        for client in self.clients.values() {
+            let _ = client.tx.send(message.clone());
+        }
+    }
+}
+
+fn sync_message(rel: &str, snapshot: &Snapshot, editors: usize) -> Value {
+    json!({
+        "type": "sync",
+        "path": rel,
+        "text": snapshot.text,
+        "html": render::to_html(&snapshot.text),
+        "rich": rich_editable(&snapshot.text),
+        "editors": editors,
+    })
+}
+
+/// Edits turning `old` into `new`, as `(byte offset in old, bytes removed,
+/// text inserted)`, last first so that offsets stay valid when applied in order.
+fn text_edits<'a>(old: &str, new: &'a str) -> Vec<(usize, usize, &'a str)> {
+    let old_chars: Vec<char> = old.chars().collect();
+    let new_chars: Vec<char> = new.chars().collect();
+    let old_offsets = char_offsets(old);
+    let new_offsets = char_offsets(new);
+    // au-delà, la différence est moins fine mais reste correcte
+    let deadline = Instant::now() + Duration::from_millis(100);
+    let ops =
+        capture_diff_slices_deadline(Algorithm::Myers, &old_chars, &new_chars, Some(deadline));
+
+    let mut edits = Vec::new();
+    for op in ops.iter().rev() {
+        let (old_index, old_len, new_index, new_len) = match *op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete {
+                old_index,
+                old_len,
+                new_index,
+            } => (old_index, old_len, new_index, 0),
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => (old_index, 0, new_index, new_len),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => (old_index, old_len, new_index, new_len),
+        };
+        let at = old_offsets[old_index];
+        let removed = old_offsets[old_index + old_len] - at;
+        let inserted = &new[new_offsets[new_index]..new_offsets[new_index + new_len]];
+        edits.push((at, removed, inserted));
+    }
+    edits
+}
+
+/// Byte offset of every char, plus the length of `s`.
+fn char_offsets(s: &str) -> Vec<usize> {
+    s.char_indices().map(|(i, _)| i).chain([s.len()]).collect()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn invalid(e: impl ToString) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, Receiver};
+
+    fn messages(rx: &Receiver<String>) -> Vec<Value> {
+        rx.try_iter()
+            .map(|m| serde_json::from_str(&m).unwrap())
+            .collect()
+    }
+
+    fn last_sync(rx: &Receiver<String>) -> Value {
+        messages(rx)
+            .into_iter()
+            .rfind(|m| m["type"] == "sync")
+            .unwrap()
+    }
+
+    #[test]
+    fn merges_concurrent_edits_and_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Travail")).unwrap();
+        let file = dir.path().join("Travail/a.md");
+        fs::write(&file, "un\ndeux\ntrois\n").unwrap();
+        let hub = Hub::new(Vault::new(dir.path()));
+
+        let (tx1, rx1) = channel();
+        let (tx2, rx2) = channel();
+        hub.join("Travail/a.md", 1, tx1).unwrap();
+        hub.join("Travail/a.md", 2, tx2).unwrap();
+        let base1 = last_sync(&rx1)["version"].as_u64().unwrap();
+        let base2 = last_sync(&rx2)["version"].as_u64().unwrap();
+
+        // deux éditions faites sur la même version
+        hub.edit("Travail/a.md", 1, base1, "un !\ndeux\ntrois\n")
+            .unwrap();
+        hub.edit("Travail/a.md", 2, base2, "un\ndeux\ntrois ?\n")
+            .unwrap();
+        let merged = "un !\ndeux\ntrois ?\n";
+        assert_eq!(fs::read_to_string(&file).unwrap(), merged);
+        let received = messages(&rx1);
+        let sync = received.iter().rfind(|m| m["type"] == "sync").unwrap();
+        assert_eq!(sync["text"], merged);
+
+        // l'accusé de réception permet d'enchaîner sur sa propre édition
+        let ack = received.iter().find(|m| m["type"] == "ack").unwrap();
+        let acked = ack["version"].as_u64().unwrap();
+        hub.edit("Travail/a.md", 1, acked, "un !\ndeux\ntrois\nquatre\n")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "un !\ndeux\ntrois ?\nquatre\n"
+        );
+
+        // modification extérieure (application de bureau)
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(&file, "zéro\nun !\ndeux\ntrois ?\nquatre\n").unwrap();
+        hub.reconcile();
+        assert_eq!(
+            last_sync(&rx2)["text"],
+            "zéro\nun !\ndeux\ntrois ?\nquatre\n"
+        );
+
+        hub.leave("Travail/a.md", 1);
+        hub.leave("Travail/a.md", 2);
+        assert!(hub.docs.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_connection_keeps_one_client_id() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "un\n").unwrap();
+        let hub = Hub::new(Vault::new(dir.path()));
+        let (tx, rx) = channel();
+        hub.join("a.md", 7, tx).unwrap();
+        let first = last_sync(&rx)["version"].as_u64().unwrap();
+
+        let mut base = first;
+        for text in ["un\ndeux\n", "un\ndeux\ntrois\n"] {
+            hub.edit("a.md", 7, base, text).unwrap();
+            let ack = messages(&rx).into_iter().find(|m| m["type"] == "ack");
+            base = ack.unwrap()["version"].as_u64().unwrap();
+        }
+        let docs = hub.docs.lock().unwrap();
+        let clients: Vec<u64> = docs["a.md"]
+            .doc
+            .transact()
+            .state_vector()
+            .iter()
+            .map(|(id, _)| id.get())
+            .collect();
+        drop(docs);
+        let mut clients = clients;
+        clients.sort();
+        assert_eq!(clients, [FILE_CLIENT, 7]);
+
+        // une version d'avant ses propres éditions ne peut plus servir de base
+        let err = hub.edit("a.md", 7, first, "un\nquatre\n").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn edits_use_byte_offsets() {
+        let edits = text_edits("café au lait", "café noir au lait");
+        assert_eq!(edits, vec![(6, 0, "noir ")]);
+    }
+}
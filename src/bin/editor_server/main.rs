@@ -0,0 +1,227 @@
+use auth::Auth;
+use notes_app::vault::Vault;
+use std::error::Error;
+use std::io::{self, Cursor, ErrorKind, Read};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use std::{env, fs};
+use tiny_http::{Header, Method, Request, Response, Server, SslConfig};
+
+mod api;
+mod args;
+mod assets;
+mod auth;
+mod collab;
+mod ws;
+
+fn main() {
+    let args = match args::parse(env::args().skip(1), |name| env::var(name).ok()) {
+        Ok(args) if args.help => {
+            println!("{}", args::USAGE);
+            return;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("editor_server: {e}\n\n{}", args::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(&args) {
+        eprintln!("editor_server: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &args::Args) -> Result<(), Box<dyn Error>> {
+    if !args.vault.is_dir() {
+        return Err(format!("{} is not a directory", args.vault.display()).into());
+    }
+    let vault = Vault::new(&args.vault);
+    let (mut auth, created) = Auth::load(&vault, args.cert.is_some())
+        .map_err(|e| format!("cannot load the access tokens: {e}"))?;
+    if args.print_token {
+        println!("{}", auth.secret());
+        return Ok(());
+    }
+    if let Some(name) = &args.read_only_token {
+        println!("{}", auth.add_read_only_token(name)?);
+        return Ok(());
+    }
+
+    let ssl = match (&args.cert, &args.key) {
+        (Some(cert), Some(key)) => Some(SslConfig {
+            certificate: fs::read(cert).map_err(|e| format!("{}: {e}", cert.display()))?,
+            private_key: fs::read(key).map_err(|e| format!("{}: {e}", key.display()))?,
+        }),
+        _ => None,
+    };
+    let ws_tls = ssl.as_ref().map(tls_config).transpose()?;
+    let scheme = if ssl.is_some() { "https" } else { "http" };
+
+    let listener = bind(&args.host, args.port, args.strict_port)
+        .map_err(|e| format!("cannot listen on {}:{}: {e}", args.host, args.port))?;
+    let addr = listener.local_addr()?;
+    let server = Arc::new(Server::from_listener(listener, ssl).map_err(|e| e.to_string())?);
+
+    let auth = Arc::new(Mutex::new(auth));
+    let hub = Arc::new(collab::Hub::new(vault.clone()));
+    // même hôte et même certificat que le serveur HTTP
+    let ws_port = args
+        .ws_port
+        .unwrap_or(addr.port().checked_add(1).unwrap_or(0));
+    let ws_listener = bind(&args.host, ws_port, args.strict_port)
+        .map_err(|e| format!("cannot listen on {}:{ws_port}: {e}", args.host))?;
+    let collab_port = ws::spawn(ws_listener, ws_tls, Arc::clone(&hub), Arc::clone(&auth))
+        .map_err(|e| format!("cannot start the collaboration server: {e}"))?;
+    // les modifications faites hors du serveur (application de bureau, PUT) sont fusionnées
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(500));
+        hub.reconcile();
+    });
+
+    // SIGINT / SIGTERM : on termine la requête en cours puis on sort de la boucle
+    let stop = Arc::clone(&server);
+    ctrlc::set_handler(move || stop.unblock())?;
+
+    println!("Server running at {scheme}://{addr}/");
+    println!("Serving notes from {}", vault.root().display());
+    let ws_scheme = if scheme == "https" { "wss" } else { "ws" };
+    println!(
+        "Collaborative editing at {ws_scheme}://{}:{collab_port}/",
+        addr.ip()
+    );
+    if created {
+        let auth = auth.lock().unwrap();
+        println!(
+            "Access token (also in {}): {}",
+            auth.path().display(),
+            auth.secret()
+        );
+    } else {
+        println!("Access token: editor_server --print-token");
+    }
+    if !addr.ip().is_loopback() && scheme == "http" {
+        eprintln!(
+            "Warning: tokens travel unencrypted over HTTP; use --cert and --key on a network"
+        );
+    }
+
+    for request in server.incoming_requests() {
+        route(&vault, &auth, collab_port, request);
+    }
+    println!("Server stopped");
+    Ok(())
+}
+
+/// Binds `host:port`, falling back to a port picked by the system when it is
+/// already in use (unless `strict`).
+fn bind(host: &str, port: u16, strict: bool) -> io::Result<TcpListener> {
+    match TcpListener::bind((host, port)) {
+        Err(e) if e.kind() == ErrorKind::AddrInUse && !strict => {
+            let listener = TcpListener::bind((host, 0))?;
+            eprintln!(
+                "Port {port} is busy, using {} instead",
+                listener.local_addr()?.port()
+            );
+            Ok(listener)
+        }
+        result => result,
+    }
+}
+
+/// rustls configuration of the WebSocket listener, from the same PEM files
+/// as the HTTPS server.
+fn tls_config(ssl: &SslConfig) -> Result<Arc<rustls::ServerConfig>, Box<dyn Error>> {
+    let certs = rustls_pemfile::certs(&mut ssl.certificate.as_slice())?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut ssl.private_key.as_slice())?;
+    if keys.is_empty() {
+        keys = rustls_pemfile::rsa_private_keys(&mut ssl.private_key.as_slice())?;
+    }
+    let key = keys.pop().ok_or("no private key found in the key file")?;
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, rustls::PrivateKey(key))?;
+    Ok(Arc::new(config))
+}
+
+/// The lock on `auth` is only held to check or change the sessions, never
+/// while a request reads the vault.
+fn route(vault: &Vault, auth: &Mutex<Auth>, collab_port: u16, mut request: Request) {
+    let url = request.url().to_owned();
+    let path = url.split('?').next().unwrap_or_default();
+    let method = request.method().clone();
+
+    if path == "/login" {
+        let response = match method {
+            Method::Post => login(auth, &mut request),
+            _ => auth::login_page(false),
+        };
+        let _ = request.respond(response);
+        return;
+    }
+    let access = auth.lock().unwrap().authenticate(&request);
+    let Some(access) = access else {
+        let response = if path.starts_with("/api/") {
+            api::json_response(
+                401,
+                &serde_json::json!({ "error": "authentication required" }),
+            )
+            .with_header(header("WWW-Authenticate", "Bearer"))
+        } else {
+            redirect("/login")
+        };
+        let _ = request.respond(response);
+        return;
+    };
+
+    if path.starts_with("/api/") {
+        let response = api::handle(vault, access, collab_port, &mut request);
+        let _ = request.respond(response);
+    } else if method == Method::Post && path == "/logout" {
+        let cookie = auth.lock().unwrap().logout(&request);
+        let _ = request.respond(redirect("/login").with_header(header("Set-Cookie", &cookie)));
+    } else if method == Method::Get && path.starts_with("/render/") {
+        let _ = request.respond(api::render_note(vault, &path["/render/".len()..]));
+    } else if method == Method::Get && path.starts_with("/static/") {
+        let response = assets::serve(&path["/static/".len()..], &request);
+        let _ = request.respond(response);
+    } else if method == Method::Get && path == "/" {
+        let response = Response::from_string(assets::INDEX)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"));
+        let _ = request.respond(response);
+    } else {
+        let _ = request.respond(Response::empty(404));
+    }
+}
+
+/// `POST /login` with the form field `token`.
+fn login(auth: &Mutex<Auth>, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+    let mut form = String::new();
+    let _ = request.as_reader().take(4096).read_to_string(&mut form);
+    let token = api::query_param(&form, "token").unwrap_or_default();
+    let result = auth.lock().unwrap().login(&token);
+    match result {
+        Ok(Some(cookie)) => redirect("/").with_header(header("Set-Cookie", &cookie)),
+        Ok(None) => auth::login_page(true),
+        Err(e) => {
+            eprintln!("Login failed: {e}");
+            Response::from_data(Vec::new()).with_status_code(500)
+        }
+    }
+}
+
+fn redirect(to: &str) -> Response<Cursor<Vec<u8>>> {
+    Response::from_data(Vec::new())
+        .with_status_code(303)
+        .with_header(header("Location", to))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name, value).unwrap()
+}
@@ -0,0 +1,180 @@
+//! WebSocket endpoint of the collaborative editor. tiny_http hands out an
+//! upgraded connection as a single stream that cannot be read and written
+//! from two threads, so WebSockets get their own listener (`--ws-port`, with
+//! the TLS configuration of the HTTP server): each connection thread polls
+//! the socket with a short timeout and flushes the messages queued for it in
+//! between.
+
+use crate::auth::{Access, Auth};
+use crate::collab::Hub;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::{Message, WebSocket};
+
+/// How long a connection waits for a client message before sending the
+/// updates queued for it.
+const POLL: Duration = Duration::from_millis(50);
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    /// Start editing a note (and stop editing the previous one).
+    Open { path: String },
+    /// The whole text after editing version `base`.
+    Edit { base: u64, text: String },
+}
+
+/// Accepts WebSocket connections on `listener`, over TLS when `tls` is
+/// given, and returns its port.
+pub fn spawn(
+    listener: TcpListener,
+    tls: Option<Arc<rustls::ServerConfig>>,
+    hub: Arc<Hub>,
+    auth: Arc<Mutex<Auth>>,
+) -> io::Result<u16> {
+    let port = listener.local_addr()?.port();
+    thread::spawn(move || {
+        // l'identifiant d'une connexion est aussi son client yrs ; 0 est celui du fichier
+        let ids = AtomicU64::new(1);
+        for stream in listener.incoming().flatten() {
+            let (hub, auth, tls) = (Arc::clone(&hub), Arc::clone(&auth), tls.clone());
+            let id = ids.fetch_add(1, Ordering::Relaxed);
+            thread::spawn(move || {
+                let result = match tls {
+                    Some(config) => rustls::ServerConnection::new(config)
+                        .map_err(|e| io::Error::other(e.to_string()))
+                        .and_then(|conn| {
+                            let tcp = stream.try_clone()?;
+                            serve(
+                                rustls::StreamOwned::new(conn, stream),
+                                &tcp,
+                                id,
+                                &hub,
+                                &auth,
+                            )
+                        }),
+                    None => stream
+                        .try_clone()
+                        .and_then(|tcp| serve(stream, &tcp, id, &hub, &auth)),
+                };
+                if let Err(e) = result {
+                    eprintln!("WebSocket connection closed: {e}");
+                }
+            });
+        }
+    });
+    Ok(port)
+}
+
+// la signature du callback de poignée de main est imposée par tungstenite
+#[allow(clippy::result_large_err)]
+fn serve<S: Read + Write>(
+    stream: S,
+    tcp: &TcpStream,
+    id: u64,
+    hub: &Hub,
+    auth: &Mutex<Auth>,
+) -> io::Result<()> {
+    tcp.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut access = None;
+    let mut socket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        access = authenticate(request, auth);
+        if access.is_some() {
+            return Ok(response);
+        }
+        let mut error = ErrorResponse::new(Some("authentication required".into()));
+        *error.status_mut() = tungstenite::http::StatusCode::UNAUTHORIZED;
+        Err(error)
+    })
+    .map_err(|e| io::Error::other(e.to_string()))?;
+    let Some(access) = access else {
+        return Ok(());
+    };
+    tcp.set_read_timeout(Some(POLL))?;
+
+    let (tx, rx) = channel();
+    let mut open: Option<String> = None;
+    let result = run(&mut socket, &rx, |text| {
+        let reply = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Open { path }) => {
+                if let Some(previous) = open.take() {
+                    hub.leave(&previous, id);
+                }
+                hub.join(&path, id, tx.clone()).map(|_| open = Some(path))
+            }
+            Ok(ClientMessage::Edit { .. }) if access == Access::ReadOnly => Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                "read-only access",
+            )),
+            Ok(ClientMessage::Edit { base, text }) => match &open {
+                Some(path) => hub.edit(path, id, base, &text),
+                None => Err(io::Error::new(ErrorKind::InvalidInput, "no note open")),
+            },
+            Err(e) => Err(io::Error::new(ErrorKind::InvalidInput, e)),
+        };
+        if let Err(e) = reply {
+            let _ = tx.send(json!({ "type": "error", "error": e.to_string() }).to_string());
+        }
+    });
+    if let Some(path) = open {
+        hub.leave(&path, id);
+    }
+    result
+}
+
+/// Reads client messages and writes the queued ones until the connection closes.
+fn run<S: Read + Write>(
+    socket: &mut WebSocket<S>,
+    queued: &Receiver<String>,
+    mut on_message: impl FnMut(&str),
+) -> io::Result<()> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => on_message(text.as_str()),
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(())
+            }
+            Err(e) => return Err(io::Error::other(e.to_string())),
+        }
+        while let Ok(message) = queued.try_recv() {
+            socket
+                .send(Message::text(message))
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+    }
+}
+
+/// Checks the session cookie (or bearer token) of the upgrade request, and
+/// that browsers open it from a page of this host.
+fn authenticate(request: &Request, auth: &Mutex<Auth>) -> Option<Access> {
+    let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok());
+    if let Some(origin) = header("Origin") {
+        let host = header("Host").map(strip_port)?;
+        let origin = origin.split("://").nth(1).map(strip_port)?;
+        if origin != host {
+            return None;
+        }
+    }
+    auth.lock()
+        .unwrap()
+        .check(header("Authorization"), header("Cookie"))
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name,
+        _ => host,
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: md_watch [options] <input>...
+       md_watch --site <dir> [options]
+
+Inputs may be Markdown files, directories (converted recursively) or glob
+patterns such as 'notes/**/*.md'.
+
+Options:
+  -o, --out <dir>        write HTML into <dir>, mirroring the input tree
+  -t, --template <file>  HTML template with {{title}} and {{content}} placeholders
+      --site <dir>       render a whole folder of notes as a static website
+      --once             convert once and exit instead of watching
+      --serve            preview the HTML on http://127.0.0.1 with live reload
+      --port <port>      port of the preview server (default 8080)
+      --debounce <ms>    wait for <ms> of quiet before converting (default 200)
+  -h, --help             print this help";
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub inputs: Vec<String>,
+    pub out: Option<PathBuf>,
+    pub template: Option<PathBuf>,
+    pub site: Option<PathBuf>,
+    pub once: bool,
+    pub serve: bool,
+    pub port: u16,
+    pub debounce: Duration,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            out: None,
+            template: None,
+            site: None,
+            once: false,
+            serve: false,
+            port: 8080,
+            debounce: Duration::from_millis(200),
+            help: false,
+        }
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} expects a value"));
+        match arg.as_str() {
+            "-o" | "--out" => parsed.out = Some(value(&arg)?.into()),
+            "-t" | "--template" => parsed.template = Some(value(&arg)?.into()),
+            "--site" => parsed.site = Some(value(&arg)?.into()),
+            "--once" => parsed.once = true,
+            "--serve" => parsed.serve = true,
+            "--port" => {
+                let port = value(&arg)?;
+                parsed.port = port
+                    .parse()
+                    .map_err(|_| format!("invalid --port value: {port}"))?;
+            }
+            "--debounce" => {
+                let ms = value(&arg)?;
+                let ms: u64 = ms
+                    .parse()
+                    .map_err(|_| format!("invalid --debounce value: {ms}"))?;
+                parsed.debounce = Duration::from_millis(ms);
+            }
+            "-h" | "--help" => parsed.help = true,
+            s if s.starts_with('-') && s.len() > 1 => return Err(format!("unknown option: {s}")),
+            _ => parsed.inputs.push(arg),
+        }
+    }
+
+    if parsed.help {
+        return Ok(parsed);
+    }
+    if parsed.site.is_some() {
+        if !parsed.inputs.is_empty() {
+            return Err("--site takes no other input".into());
+        }
+        if parsed.template.is_some() {
+            return Err("--template cannot be used with --site".into());
+        }
+        if parsed.serve {
+            return Err("--serve cannot be used with --site".into());
+        }
+    } else if parsed.inputs.is_empty() {
+        return Err("no input given".into());
+    }
+    if parsed.serve && parsed.once {
+        return Err("--serve keeps watching and cannot be used with --once".into());
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Result<Args, String> {
+        parse(s.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_inputs_and_options() {
+        let a = args("notes/ 'x/**/*.md' -o out --template t.html --once --debounce 50").unwrap();
+        assert_eq!(a.inputs, vec!["notes/", "'x/**/*.md'"]);
+        assert_eq!(a.out, Some(PathBuf::from("out")));
+        assert_eq!(a.template, Some(PathBuf::from("t.html")));
+        assert!(a.once);
+        assert_eq!(a.debounce, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        assert!(args("").is_err());
+        assert!(args("a.md --out").is_err());
+        assert!(args("a.md --frobnicate").is_err());
+        assert!(args("--site notes a.md").is_err());
+        assert!(args("--site notes").is_ok());
+        assert!(args("a.md --serve --once").is_err());
+        assert!(args("a.md --port http").is_err());
+    }
+}
@@ -0,0 +1,385 @@
+use notes_app::watch::FileWatcher;
+use notes_app::{crypt, render};
+use pulldown_cmark::escape::escape_html;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, io};
+
+mod args;
+mod serve;
+mod site;
+
+fn main() {
+    let args = match args::parse(env::args().skip(1)) {
+        Ok(args) if args.help => {
+            println!("{}", args::USAGE);
+            return;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("md_watch: {e}\n\n{}", args::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let result = match &args.site {
+        Some(dir) => run_site(dir, &args),
+        None => run_convert(&args),
+    };
+    if let Err(e) = result {
+        eprintln!("md_watch: {e}");
+        std::process::exit(1);
+    }
+}
+
+/// One command-line input: a file, a directory converted recursively, or a
+/// glob pattern. `base` is the directory the output tree is mirrored from.
+enum Source {
+    File(PathBuf),
+    Dir(PathBuf),
+    Glob {
+        base: PathBuf,
+        pattern: glob::Pattern,
+    },
+}
+
+impl Source {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let path = Path::new(input);
+        if path.is_file() {
+            return Ok(Source::File(fs::canonicalize(path)?));
+        }
+        if path.is_dir() {
+            return Ok(Source::Dir(fs::canonicalize(path)?));
+        }
+        // motif glob : la base est la partie sans caractères spéciaux
+        let literal: PathBuf = path
+            .components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+            .collect();
+        if literal == path {
+            return Err(format!("{input}: no such file or directory").into());
+        }
+        let base = if literal.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            literal.clone()
+        };
+        let base = fs::canonicalize(&base)?;
+        let rest = path.strip_prefix(&literal)?;
+        let pattern = glob::Pattern::new(&base.join(rest).to_string_lossy())?;
+        Ok(Source::Glob { base, pattern })
+    }
+
+    fn base(&self) -> &Path {
+        match self {
+            Source::File(file) => file.parent().unwrap_or(Path::new("/")),
+            Source::Dir(dir) => dir,
+            Source::Glob { base, .. } => base,
+        }
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        match self {
+            Source::File(file) => vec![file.clone()],
+            Source::Dir(dir) => {
+                let mut files = Vec::new();
+                collect_markdown(dir, &mut files);
+                files
+            }
+            Source::Glob { pattern, .. } => glob::glob(pattern.as_str())
+                .map(|paths| paths.flatten().filter(|p| p.is_file()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Source::File(file) => path == file,
+            Source::Dir(dir) => path.starts_with(dir) && is_markdown(path),
+            Source::Glob { pattern, .. } => pattern.matches_path(path),
+        }
+    }
+
+    /// Directory to watch for this input, and whether recursively. A file is
+    /// watched through its directory: editors save by renaming a new file
+    /// over the old one, and a watch on the old file would end there.
+    /// [`Source::matches`] keeps only the events of the file itself.
+    fn watched(&self) -> (&Path, bool) {
+        match self {
+            Source::File(_) => (self.base(), false),
+            Source::Dir(dir) => (dir, true),
+            Source::Glob { base, .. } => (base, true),
+        }
+    }
+}
+
+struct Converter {
+    sources: Vec<Source>,
+    out: Option<PathBuf>,
+    template: Option<String>,
+}
+
+impl Converter {
+    /// `note.md` → `note.html`, next to the source or mirrored under `--out`.
+    fn output(&self, source: &Source, file: &Path) -> PathBuf {
+        match &self.out {
+            Some(out) => out
+                .join(file.strip_prefix(source.base()).unwrap_or(file))
+                .with_extension("html"),
+            None => file.with_extension("html"),
+        }
+    }
+
+    /// Every file currently matched by the inputs, with its URL in the
+    /// preview server (the output path relative to `--out`).
+    fn pages(&self) -> Vec<(String, PathBuf)> {
+        let mut pages = Vec::new();
+        for source in &self.sources {
+            for file in source.files() {
+                let url = file
+                    .strip_prefix(source.base())
+                    .unwrap_or(&file)
+                    .with_extension("html")
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                pages.push((url, file));
+            }
+        }
+        pages
+    }
+
+    fn render(&self, file: &Path) -> io::Result<String> {
+        let mut md = fs::read_to_string(file)?;
+        if crypt::is_encrypted(&md) {
+            md = crypt::PLACEHOLDER.to_owned();
+        }
+        let mut html_out = render::to_html(&md);
+
+        if let Some(template) = &self.template {
+            let title = render::first_heading(&md).unwrap_or_else(|| {
+                file.file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            let mut escaped = String::new();
+            let _ = escape_html(&mut escaped, &title);
+            html_out = template
+                .replace("{{title}}", &escaped)
+                .replace("{{content}}", &html_out);
+        }
+        Ok(html_out)
+    }
+
+    fn convert(&self, source: &Source, file: &Path) -> io::Result<()> {
+        let html_out = self.render(file)?;
+        let html_path = self.output(source, file);
+        if let Some(dir) = html_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&html_path, html_out)?;
+        println!("Updated {}", html_path.display());
+        Ok(())
+    }
+
+    fn convert_all(&self) {
+        for source in &self.sources {
+            for file in source.files() {
+                if let Err(e) = self.convert(source, &file) {
+                    eprintln!("{}: {e}", file.display());
+                }
+            }
+        }
+    }
+
+    /// Reconverts the changed files, and drops the output of deleted ones.
+    /// Returns whether any input was affected.
+    fn update(&self, paths: &[PathBuf]) -> bool {
+        let mut touched = false;
+        for path in paths {
+            let Some(source) = self.sources.iter().find(|s| s.matches(path)) else {
+                continue;
+            };
+            touched = true;
+            if path.is_file() {
+                if let Err(e) = self.convert(source, path) {
+                    eprintln!("{}: {e}", path.display());
+                }
+            } else if !path.exists() {
+                let _ = fs::remove_file(self.output(source, path));
+            }
+        }
+        touched
+    }
+}
+
+fn run_convert(args: &args::Args) -> Result<(), Box<dyn Error>> {
+    let sources = args
+        .inputs
+        .iter()
+        .map(|i| Source::parse(i))
+        .collect::<Result<Vec<_>, _>>()?;
+    let template = match &args.template {
+        Some(path) => {
+            Some(fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?)
+        }
+        None => None,
+    };
+    let converter = Arc::new(Converter {
+        sources,
+        out: args.out.clone(),
+        template,
+    });
+    converter.convert_all();
+    if args.once {
+        return Ok(());
+    }
+    let reloads = Arc::new(serve::Reloads::default());
+    if args.serve {
+        let url = serve::spawn(args.port, converter.clone(), reloads.clone())?;
+        println!("Preview at {url}");
+    }
+
+    let mut watcher = FileWatcher::new()?;
+    watch_sources(&converter.sources, &mut watcher)?;
+    while let Some(paths) = watcher.next_batch(args.debounce) {
+        if converter.update(&paths) {
+            reloads.bump();
+        }
+    }
+    Ok(())
+}
+
+/// Watches the directories of `sources`, each once: a directory already
+/// watched recursively is not watched again, which would drop its subtree.
+fn watch_sources(sources: &[Source], watcher: &mut FileWatcher) -> notify::Result<()> {
+    let mut dirs: Vec<(&Path, bool)> = sources.iter().map(Source::watched).collect();
+    // récursifs d'abord, parents avant enfants
+    dirs.sort_by_key(|(dir, recursive)| (!recursive, dir.components().count()));
+    let mut watched: Vec<(&Path, bool)> = Vec::new();
+    for (dir, recursive) in dirs {
+        let covered = watched
+            .iter()
+            .any(|(w, r)| *w == dir || (*r && dir.starts_with(w)));
+        if !covered {
+            watcher.watch(dir, recursive)?;
+            watched.push((dir, recursive));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the whole site once, then rebuilds incrementally on every change.
+fn run_site(root: &Path, args: &args::Args) -> Result<(), Box<dyn Error>> {
+    let out = args.out.clone().unwrap_or_else(|| PathBuf::from("site"));
+    // chemins absolus, pour comparer avec ceux des événements
+    let root = fs::canonicalize(root)?;
+    fs::create_dir_all(&out)?;
+    let out = fs::canonicalize(out)?;
+    if root.starts_with(&out) {
+        return Err("the output directory must not contain the notes".into());
+    }
+    let mut site = site::Site::new(root.clone(), out);
+    site.build()?;
+    if args.once {
+        return Ok(());
+    }
+
+    let mut watcher = FileWatcher::new()?;
+    watcher.watch(&root, true)?;
+    while let Some(paths) = watcher.next_batch(args.debounce) {
+        if let Err(e) = site.update(&paths) {
+            eprintln!("{e}");
+        }
+    }
+    Ok(())
+}
+
+fn collect_markdown(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_markdown(&path, files);
+        } else if is_markdown(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "md")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Waits up to two seconds for an event on `file`.
+    fn changed(watcher: &FileWatcher, source: &Source, file: &Path) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(2) {
+            if watcher
+                .changes()
+                .iter()
+                .any(|p| p == file && source.matches(p))
+            {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn files_saved_by_rename_stay_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("note.md");
+        fs::write(&file, "# un").unwrap();
+        let source = Source::parse(file.to_str().unwrap()).unwrap();
+        let file = fs::canonicalize(&file).unwrap();
+        let mut watcher = FileWatcher::new().unwrap();
+        watch_sources(std::slice::from_ref(&source), &mut watcher).unwrap();
+
+        for text in ["# deux", "# trois"] {
+            let tmp = dir.path().join(".note.md.swp");
+            fs::write(&tmp, text).unwrap();
+            fs::rename(&tmp, &file).unwrap();
+            assert!(changed(&watcher, &source, &file), "{text}");
+        }
+    }
+
+    #[test]
+    fn preview_reloads_after_rename_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("note.md");
+        fs::write(&file, "# un").unwrap();
+        let converter = Converter {
+            sources: vec![Source::parse(file.to_str().unwrap()).unwrap()],
+            out: Some(dir.path().join("html")),
+            template: None,
+        };
+        let file = fs::canonicalize(&file).unwrap();
+        let mut watcher = FileWatcher::new().unwrap();
+        watch_sources(&converter.sources, &mut watcher).unwrap();
+
+        for text in ["# deux", "# trois"] {
+            let tmp = dir.path().join(".note.md.swp");
+            fs::write(&tmp, text).unwrap();
+            fs::rename(&tmp, &file).unwrap();
+            let start = Instant::now();
+            let mut reloaded = false;
+            while !reloaded && start.elapsed() < Duration::from_secs(2) {
+                reloaded = converter.update(&watcher.changes());
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            assert!(reloaded, "{text}");
+            assert!(converter.render(&file).unwrap().contains(&text[2..]));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::Converter;
+use notes_app::url::{percent_decode, percent_encode_path};
+use pulldown_cmark::escape::escape_html;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How long a reload poll is held open before the browser asks again.
+const POLL_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Counter bumped after every conversion; browsers long-poll it and reload
+/// the page when it moves.
+#[derive(Default)]
+pub struct Reloads {
+    version: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl Reloads {
+    pub fn bump(&self) {
+        let mut version = self.version.lock().unwrap();
+        *version += 1;
+        self.changed.notify_all();
+    }
+
+    fn current(&self) -> u64 {
+        *self.version.lock().unwrap()
+    }
+
+    /// Blocks until the version differs from `seen`, or the timeout expires.
+    fn wait_newer(&self, seen: u64) -> u64 {
+        let version = self.version.lock().unwrap();
+        let (version, _) = self
+            .changed
+            .wait_timeout_while(version, POLL_TIMEOUT, |v| *v == seen)
+            .unwrap();
+        *version
+    }
+}
+
+/// Starts the preview server on `127.0.0.1:port` in a background thread
+/// and returns its URL.
+pub fn spawn(port: u16, converter: Arc<Converter>, reloads: Arc<Reloads>) -> io::Result<String> {
+    let server = Server::http(("127.0.0.1", port)).map_err(io::Error::other)?;
+    let url = format!("http://{}/", server.server_addr());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            if request.method() != &Method::Get {
+                let _ = request.respond(Response::empty(405));
+                continue;
+            }
+            if request.url().starts_with("/__reload") {
+                let reloads = reloads.clone();
+                thread::spawn(move || poll(request, &reloads));
+            } else {
+                serve_page(request, &converter, &reloads);
+            }
+        }
+    });
+    Ok(url)
+}
+
+fn poll(request: Request, reloads: &Reloads) {
+    let seen = request
+        .url()
+        .split_once("since=")
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(0);
+    let version = reloads.wait_newer(seen);
+    let _ = request.respond(
+        Response::from_string(version.to_string()).with_header(header("Cache-Control", "no-store")),
+    );
+}
+
+fn serve_page(request: Request, converter: &Converter, reloads: &Reloads) {
+    let pages = converter.pages();
+    let path = request.url().split('?').next().unwrap_or("/");
+    let path = percent_decode(path.trim_start_matches('/'));
+
+    let version = reloads.current();
+    let html = if path.is_empty() && pages.len() != 1 {
+        Ok(index(&pages))
+    } else {
+        match pages
+            .iter()
+            .find(|(url, _)| path.is_empty() || *url == path)
+        {
+            Some((_, file)) => converter.render(file),
+            None => {
+                let _ = request.respond(Response::from_string("not found").with_status_code(404));
+                return;
+            }
+        }
+    };
+
+    let response = match html {
+        Ok(html) => Response::from_string(with_reload_script(&html, version))
+            .with_header(header("Content-Type", "text/html; charset=utf-8"))
+            .with_header(header("Cache-Control", "no-store")),
+        Err(e) => Response::from_string(e.to_string()).with_status_code(500),
+    };
+    let _ = request.respond(response);
+}
+
+/// List of the pages, linked by their encoded URL.
+fn index(pages: &[(String, PathBuf)]) -> String {
+    let mut list = String::from(
+        "<!DOCTYPE html>\n<meta charset=\"UTF-8\" />\n<title>md_watch</title>\n<ul>\n",
+    );
+    for (url, _) in pages {
+        let mut text = String::new();
+        let _ = escape_html(&mut text, url);
+        list.push_str(&format!(
+            "<li><a href=\"/{}\">{text}</a></li>\n",
+            percent_encode_path(url)
+        ));
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+/// Injects the long-polling script that reloads the page after a conversion.
+fn with_reload_script(html: &str, version: u64) -> String {
+    let script = format!(
+        r#"<script>
+(async () => {{
+  for (;;) {{
+    try {{
+      const r = await fetch('/__reload?since={version}', {{ cache: 'no-store' }});
+      if (parseInt(await r.text(), 10) !== {version}) {{ location.reload(); return; }}
+    }} catch (e) {{
+      await new Promise(res => setTimeout(res, 1000));
+    }}
+  }}
+}})();
+</script>
+"#
+    );
+    match html.rfind("</body>") {
+        Some(i) => format!("{}{script}{}", &html[..i], &html[i..]),
+        None => format!("{html}\n{script}"),
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_goes_before_body_end() {
+        let html = with_reload_script("<html><body><p>x</p></body></html>", 3);
+        assert!(html.ends_with("</script>\n</body></html>"));
+        assert!(html.contains("since=3"));
+    }
+
+    #[test]
+    fn index_links_are_escaped_and_encoded() {
+        let pages = [("a <b>/c #1?.html".to_owned(), PathBuf::from("x.md"))];
+        let html = index(&pages);
+        assert!(
+            html.contains("<a href=\"/a%20%3Cb%3E/c%20%231%3F.html\">a &lt;b&gt;/c #1?.html</a>")
+        );
+    }
+}
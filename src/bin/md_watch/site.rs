@@ -0,0 +1,549 @@
+use notes_app::crypt;
+use notes_app::render::{self, Inline};
+use notes_app::url::{percent_encode, percent_encode_path};
+use pulldown_cmark::{escape::escape_html, html, Event, Tag};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory of the note pages: the generated pages and files (`index.html`,
+/// `tags/`, `style.css`…) sit beside it, so no note can overwrite them.
+const NOTES: &str = "notes";
+
+const STYLE: &str = r#"html, body { margin: 0; }
+body { display: flex; min-height: 100vh; color: #222; background: #fafafa;
+  font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, Oxygen,
+  Ubuntu, Cantarell, "Open Sans", "Helvetica Neue", sans-serif; line-height: 1.6; }
+nav { width: 16rem; flex-shrink: 0; padding: 1rem; background: #f0f0f0;
+  border-right: 1px solid #ddd; font-size: 0.9rem; }
+nav ul { list-style: none; padding-left: 0.8rem; margin: 0.2rem 0; }
+nav summary { cursor: pointer; font-weight: 600; }
+nav a.current { font-weight: 700; }
+nav input { width: 100%; box-sizing: border-box; padding: 0.3rem; margin-bottom: 0.5rem; }
+main { flex: 1; max-width: 46rem; padding: 2rem 2.5rem; }
+a { color: #1a6dcc; }
+a.broken { color: #c33; text-decoration: line-through; }
+a.tag { background: #e4eefa; border-radius: 4px; padding: 0 0.3em; text-decoration: none; }
+pre { background: #2e3440; color: #eceff4; border-radius: 6px; padding: 1rem; overflow-x: auto; }
+blockquote { border-left: 4px solid #dadada; margin-left: 0; padding-left: 1rem; color: #555; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; }
+img { max-width: 100%; }
+"#;
+
+const SEARCH_JS: &str = r#"(() => {
+  const input = document.getElementById('search');
+  const results = document.getElementById('results');
+  const root = document.body.dataset.root;
+  let index = null;
+  input.addEventListener('input', async () => {
+    if (!index) index = await fetch(root + 'search-index.json').then(r => r.json());
+    const q = input.value.trim().toLowerCase();
+    results.innerHTML = '';
+    if (!q) return;
+    index
+      .filter(e => e.title.toLowerCase().includes(q) || e.text.toLowerCase().includes(q)
+        || e.tags.some(t => t.includes(q)))
+      .slice(0, 20)
+      .forEach(e => {
+        const li = document.createElement('li');
+        const a = document.createElement('a');
+        a.href = root + e.url;
+        a.textContent = e.title;
+        li.appendChild(a);
+        results.appendChild(li);
+      });
+  });
+})();
+"#;
+
+/// One Markdown note of the vault, as needed to render the site.
+#[derive(PartialEq)]
+struct Page {
+    title: String,
+    tags: BTreeSet<String>,
+    markdown: String,
+}
+
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    title: &'a str,
+    url: String,
+    tags: &'a BTreeSet<String>,
+    text: String,
+}
+
+/// Static website generated from a vault (or any folder) of notes.
+/// Pages are keyed by their path relative to `root`.
+pub struct Site {
+    root: PathBuf,
+    out: PathBuf,
+    pages: BTreeMap<PathBuf, Page>,
+}
+
+impl Site {
+    pub fn new(root: PathBuf, out: PathBuf) -> Self {
+        Self {
+            root,
+            out,
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// Scans the whole vault and writes every page.
+    pub fn build(&mut self) -> io::Result<()> {
+        self.pages.clear();
+        let mut files = Vec::new();
+        collect_markdown(&self.root, &self.out, &mut files)?;
+        for path in files {
+            let rel = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            self.pages.insert(rel, load(&path)?);
+        }
+        self.write_all()
+    }
+
+    /// Rebuilds only what `changed` affects: the modified notes, plus the
+    /// tag pages when tags moved, or everything when notes were added,
+    /// removed or renamed (navigation and wiki-links change on every page).
+    pub fn update(&mut self, changed: &[PathBuf]) -> io::Result<()> {
+        let mut structural = false;
+        let mut tags_changed = false;
+        let mut dirty = Vec::new();
+
+        for path in changed {
+            if path.starts_with(&self.out) {
+                continue;
+            }
+            if !is_markdown(path) {
+                if path.is_dir() || !path.exists() {
+                    // dossier créé, renommé ou supprimé
+                    return self.build();
+                }
+                continue;
+            }
+            let rel = path.strip_prefix(&self.root).unwrap_or(path).to_path_buf();
+            if path.exists() {
+                let page = load(path)?;
+                match self.pages.get(&rel) {
+                    None => structural = true,
+                    Some(old) if *old == page => continue,
+                    Some(old) => {
+                        structural |= old.title != page.title;
+                        tags_changed |= old.tags != page.tags;
+                    }
+                }
+                self.pages.insert(rel.clone(), page);
+                dirty.push(rel);
+            } else if self.pages.remove(&rel).is_some() {
+                let _ = fs::remove_file(self.page_file(&rel));
+                structural = true;
+            }
+        }
+
+        if structural {
+            return self.write_all();
+        }
+        for rel in &dirty {
+            self.write_page(rel)?;
+            println!("Updated {}", self.page_file(rel).display());
+        }
+        if tags_changed {
+            self.write_tags()?;
+        }
+        if !dirty.is_empty() {
+            self.write_search_index()?;
+        }
+        Ok(())
+    }
+
+    fn write_all(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.out)?;
+        fs::write(self.out.join("style.css"), STYLE)?;
+        fs::write(self.out.join("search.js"), SEARCH_JS)?;
+        for rel in self.pages.keys() {
+            self.write_page(rel)?;
+        }
+        self.write_tags()?;
+        self.write_index()?;
+        self.write_search_index()?;
+        println!(
+            "Site generated in {} ({} notes)",
+            self.out.display(),
+            self.pages.len()
+        );
+        Ok(())
+    }
+
+    /// File generated for the note at `rel`.
+    fn page_file(&self, rel: &Path) -> PathBuf {
+        self.out.join(NOTES).join(rel).with_extension("html")
+    }
+
+    fn write_page(&self, rel: &Path) -> io::Result<()> {
+        let page = &self.pages[rel];
+        // `notes/` plus les dossiers de la note
+        let depth = rel.components().count();
+        let prefix = "../".repeat(depth);
+        let mut body = render_markdown(
+            &page.markdown,
+            |target| self.resolve(target).map(|r| format!("{prefix}{}", url(r))),
+            &prefix,
+        );
+        if !page.tags.is_empty() {
+            body.push_str("<p class=\"tags\">");
+            for tag in &page.tags {
+                body.push_str(&format!(
+                    "<a class=\"tag\" href=\"{prefix}{}\">#{}</a> ",
+                    tag_url(tag),
+                    escape(tag)
+                ));
+            }
+            body.push_str("</p>\n");
+        }
+        let out = self.page_file(rel);
+        if let Some(dir) = out.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(out, self.layout(&page.title, depth, Some(rel), &body))
+    }
+
+    fn write_index(&self) -> io::Result<()> {
+        let name = self
+            .root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Notes".into());
+        let mut body = format!("<h1>{}</h1>\n<ul>\n", escape(&name));
+        for (rel, page) in &self.pages {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                url(rel),
+                escape(&page.title)
+            ));
+        }
+        body.push_str("</ul>\n<p><a href=\"tags/index.html\">Tous les tags</a></p>\n");
+        fs::write(
+            self.out.join("index.html"),
+            self.layout(&name, 0, None, &body),
+        )
+    }
+
+    fn write_tags(&self) -> io::Result<()> {
+        let mut by_tag: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+        for (rel, page) in &self.pages {
+            for tag in &page.tags {
+                by_tag.entry(tag).or_default().push(rel);
+            }
+        }
+        let dir = self.out.join("tags");
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+
+        let mut all = String::from("<h1>Tags</h1>\n<ul>\n");
+        for (tag, rels) in &by_tag {
+            all.push_str(&format!(
+                "<li><a href=\"../{}\">#{}</a> ({})</li>\n",
+                tag_url(tag),
+                escape(tag),
+                rels.len()
+            ));
+            let mut body = format!("<h1>#{}</h1>\n<ul>\n", escape(tag));
+            for rel in rels {
+                body.push_str(&format!(
+                    "<li><a href=\"../{}\">{}</a></li>\n",
+                    url(rel),
+                    escape(&self.pages[*rel].title)
+                ));
+            }
+            body.push_str("</ul>\n");
+            fs::write(
+                dir.join(format!("{}.html", slug(tag))),
+                self.layout(&format!("#{tag}"), 1, None, &body),
+            )?;
+        }
+        all.push_str("</ul>\n");
+        fs::write(dir.join("index.html"), self.layout("Tags", 1, None, &all))
+    }
+
+    fn write_search_index(&self) -> io::Result<()> {
+        let entries: Vec<IndexEntry> = self
+            .pages
+            .iter()
+            .map(|(rel, page)| IndexEntry {
+                title: &page.title,
+                url: url(rel),
+                tags: &page.tags,
+                text: plain_text(&page.markdown),
+            })
+            .collect();
+        let json = serde_json::to_string(&entries).map_err(io::Error::other)?;
+        fs::write(self.out.join("search-index.json"), json)
+    }
+
+    /// Resolves a wiki-link target by file name, then by title (case-insensitive).
+    fn resolve(&self, target: &str) -> Option<&Path> {
+        let target = target.trim().to_lowercase();
+        self.pages
+            .iter()
+            .find(|(rel, _)| {
+                rel.file_stem()
+                    .is_some_and(|s| s.to_string_lossy().to_lowercase() == target)
+            })
+            .or_else(|| {
+                self.pages
+                    .iter()
+                    .find(|(_, p)| p.title.to_lowercase() == target)
+            })
+            .map(|(rel, _)| rel.as_path())
+    }
+
+    fn layout(&self, title: &str, depth: usize, current: Option<&Path>, body: &str) -> String {
+        let prefix = "../".repeat(depth);
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"UTF-8\" />\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n\
+             <title>{}</title>\n<link rel=\"stylesheet\" href=\"{prefix}style.css\" />\n</head>\n\
+             <body data-root=\"{prefix}\">\n<nav>\n<input id=\"search\" type=\"search\" placeholder=\"Rechercher…\" />\n\
+             <ul id=\"results\"></ul>\n<p><a href=\"{prefix}index.html\">Accueil</a> · \
+             <a href=\"{prefix}tags/index.html\">Tags</a></p>\n{}</nav>\n<main>\n{body}</main>\n\
+             <script src=\"{prefix}search.js\"></script>\n</body>\n</html>\n",
+            escape(title),
+            self.nav(&prefix, current)
+        )
+    }
+
+    /// Navigation tree mirroring the folder structure of the vault.
+    fn nav(&self, prefix: &str, current: Option<&Path>) -> String {
+        let mut html = String::from("<ul>\n");
+        let mut open: Vec<String> = Vec::new();
+        for (rel, page) in &self.pages {
+            let dirs: Vec<String> = rel
+                .parent()
+                .map(|p| {
+                    p.components()
+                        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let common = open.iter().zip(&dirs).take_while(|(a, b)| a == b).count();
+            for _ in common..open.len() {
+                html.push_str("</ul></details></li>\n");
+            }
+            open.truncate(common);
+            for dir in &dirs[common..] {
+                html.push_str(&format!(
+                    "<li><details open><summary>{}</summary><ul>\n",
+                    escape(dir)
+                ));
+                open.push(dir.clone());
+            }
+            let class = if current == Some(rel.as_path()) {
+                " class=\"current\""
+            } else {
+                ""
+            };
+            html.push_str(&format!(
+                "<li><a{class} href=\"{prefix}{}\">{}</a></li>\n",
+                url(rel),
+                escape(&page.title)
+            ));
+        }
+        for _ in &open {
+            html.push_str("</ul></details></li>\n");
+        }
+        html.push_str("</ul>\n");
+        html
+    }
+}
+
+fn collect_markdown(dir: &Path, out: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden || path.starts_with(out) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_markdown(&path, out, files)?;
+        } else if is_markdown(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "md")
+}
+
+fn load(path: &Path) -> io::Result<Page> {
+    let mut markdown = fs::read_to_string(path)?;
+    // la page d'une note chiffrée ne montre rien de son contenu
+    if crypt::is_encrypted(&markdown) {
+        markdown = crypt::PLACEHOLDER.to_owned();
+    }
+    let title = render::first_heading(&markdown).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    Ok(Page {
+        title,
+        tags: render::tags(&markdown),
+        markdown,
+    })
+}
+
+fn plain_text(md: &str) -> String {
+    let mut text = String::new();
+    for ev in render::parser(md) {
+        match ev {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Renders a note body, turning wiki-links and tags into site links.
+fn render_markdown<F>(md: &str, resolve: F, prefix: &str) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut events = Vec::new();
+    // liens, images et blocs de code gardent leur texte tel quel
+    let mut raw = false;
+    for ev in render::text_runs(md) {
+        match ev {
+            Event::Start(Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                raw = true;
+                events.push(ev);
+            }
+            Event::End(Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                raw = false;
+                events.push(ev);
+            }
+            Event::Text(t) if !raw => {
+                let mut html = String::new();
+                for piece in render::split_inline(&t) {
+                    match piece {
+                        Inline::Text(s) => html.push_str(&escape(s)),
+                        Inline::WikiLink(target, label) => match resolve(target) {
+                            Some(href) => html.push_str(&format!(
+                                "<a href=\"{href}\">{}</a>",
+                                escape(label.trim())
+                            )),
+                            None => html.push_str(&format!(
+                                "<a class=\"broken\">{}</a>",
+                                escape(label.trim())
+                            )),
+                        },
+                        Inline::Tag(tag) => html.push_str(&format!(
+                            "<a class=\"tag\" href=\"{prefix}{}\">#{}</a>",
+                            tag_url(&tag.to_lowercase()),
+                            escape(tag)
+                        )),
+                    }
+                }
+                events.push(Event::Html(html.into()));
+            }
+            ev => events.push(ev),
+        }
+    }
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+/// URL of the page generated for `rel`, from the root of the site
+/// (`Dossier/note 1.md` → `notes/Dossier/note%201.html`).
+fn url(rel: &Path) -> String {
+    let path: Vec<_> = rel
+        .with_extension("html")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    format!("{NOTES}/{}", percent_encode_path(&path.join("/")))
+}
+
+/// File name of the page of `tag`, without extension: `/` and every other
+/// character unsafe in a file name are percent-encoded, so `a/b` and `a-b`
+/// get distinct pages.
+fn slug(tag: &str) -> String {
+    percent_encode(tag)
+}
+
+/// URL of the page of `tag`, from the root of the site.
+fn tag_url(tag: &str) -> String {
+    format!("tags/{}.html", percent_encode(&slug(tag)))
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    let _ = escape_html(&mut out, s);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unresolved_wiki_links_are_marked() {
+        let html = render_markdown(
+            "[[Ailleurs]] et [[Ici]]",
+            |t| (t == "Ici").then(|| "ici.html".to_owned()),
+            "",
+        );
+        assert!(html.contains("<a class=\"broken\">Ailleurs</a>"));
+        assert!(html.contains("<a href=\"ici.html\">Ici</a>"));
+    }
+
+    fn build(files: &[(&str, &str)]) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Notes");
+        for (rel, text) in files {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        let out = dir.path().join("site");
+        Site::new(root, out.clone()).build().unwrap();
+        (dir, out)
+    }
+
+    #[test]
+    fn a_root_index_note_keeps_the_home_page() {
+        let (_dir, out) = build(&[("index.md", "# Ma page\n"), ("autre.md", "# Autre\n")]);
+        let home = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(home.contains("<h1>Notes</h1>"));
+        assert!(home.contains("href=\"notes/index.html\""));
+        let note = fs::read_to_string(out.join("notes/index.html")).unwrap();
+        assert!(note.contains("<h1>Ma page</h1>"));
+    }
+
+    #[test]
+    fn a_tags_folder_survives_the_tag_pages() {
+        let (_dir, out) = build(&[("tags/liste.md", "# Liste\n\n#idée\n")]);
+        assert!(out.join("notes/tags/liste.html").is_file());
+        let tag = fs::read_to_string(out.join("tags/id%C3%A9e.html")).unwrap();
+        assert!(tag.contains("href=\"../notes/tags/liste.html\""));
+    }
+
+    #[test]
+    fn urls_are_percent_encoded() {
+        assert_eq!(
+            url(Path::new("Q&R/note #1.md")),
+            "notes/Q%26R/note%20%231.html"
+        );
+        assert_ne!(slug("a/b"), slug("a-b"));
+        assert_eq!(tag_url("a/b"), "tags/a%252Fb.html");
+    }
+}
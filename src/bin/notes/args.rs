@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: notes [options] <command> [arguments]
+
+Scripts the notes of a vault, laid out like in the app: one directory per
+folder, one .md file per note. Notes are named by their path in the vault
+(Travail/réunion.md).
+
+Commands:
+  new --folder <folder> --title <title>
+                    create a note, reading its body from stdin
+  ls [folder]       list the folders, or the notes of <folder>
+  cat <note>        print a note
+  search <query>    search note titles and bodies
+  tag [tag]         list the #tags and their number of notes, or the notes
+                    with <tag>
+  mv <note> <dest>  move a note into the folder <dest>, or rename it when
+                    <dest> ends with .md
+  rm <note>         delete a note
+  export <note> [-o <file>] [--pdf]
+                    export a note as HTML (or PDF) into <file>, or to stdout
+
+Options:
+      --vault <dir>  the vault (default: $NOTES_VAULT, or the current directory)
+      --json         print machine-readable JSON
+  -h, --help         print this help";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    New {
+        folder: String,
+        title: String,
+    },
+    Ls(Option<String>),
+    Cat(String),
+    Search(String),
+    Tag(Option<String>),
+    Mv(String, String),
+    Rm(String),
+    Export {
+        note: String,
+        out: Option<PathBuf>,
+        pdf: bool,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub vault: PathBuf,
+    pub json: bool,
+    pub help: bool,
+    /// `None` only with `--help`.
+    pub command: Option<Command>,
+}
+
+/// Parses the arguments following the program name; `env` looks up the
+/// environment variables.
+pub fn parse(
+    args: impl IntoIterator<Item = String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Args, String> {
+    let mut vault = env("NOTES_VAULT").map(PathBuf::from);
+    let (mut json, mut help, mut pdf) = (false, false, false);
+    let (mut folder, mut title, mut out) = (None, None, None);
+    let mut words = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} expects a value"));
+        match arg.as_str() {
+            "--vault" => vault = Some(value(&arg)?.into()),
+            "--json" => json = true,
+            "-h" | "--help" => help = true,
+            "--folder" => folder = Some(value(&arg)?),
+            "--title" => title = Some(value(&arg)?),
+            "-o" | "--out" => out = Some(PathBuf::from(value(&arg)?)),
+            "--pdf" => pdf = true,
+            s if s.starts_with('-') && s.len() > 1 => return Err(format!("unknown option: {s}")),
+            _ => words.push(arg),
+        }
+    }
+    let vault = vault.unwrap_or_else(|| PathBuf::from("."));
+    if help {
+        return Ok(Args {
+            vault,
+            json,
+            help,
+            command: None,
+        });
+    }
+
+    let mut words = words.into_iter();
+    let name = words.next().ok_or("missing command")?;
+    let rest: Vec<String> = words.collect();
+    let one = |what: &str| match rest.as_slice() {
+        [arg] => Ok(arg.clone()),
+        _ => Err(format!("{name} expects {what}")),
+    };
+    let optional = || match rest.as_slice() {
+        [] => Ok(None),
+        [arg] => Ok(Some(arg.clone())),
+        _ => Err(format!("unexpected argument: {}", rest[1])),
+    };
+    let command = match name.as_str() {
+        "new" if rest.is_empty() => Command::New {
+            folder: folder.ok_or("new expects --folder")?,
+            title: title.ok_or("new expects --title")?,
+        },
+        "new" => return Err(format!("unexpected argument: {}", rest[0])),
+        "ls" => Command::Ls(optional()?),
+        "cat" => Command::Cat(one("a note")?),
+        "search" if !rest.is_empty() => Command::Search(rest.join(" ")),
+        "search" => return Err("search expects a query".into()),
+        "tag" => Command::Tag(optional()?.map(|t| t.trim_start_matches('#').to_lowercase())),
+        "mv" => match rest.as_slice() {
+            [note, dest] => Command::Mv(note.clone(), dest.clone()),
+            _ => return Err("mv expects a note and a destination".into()),
+        },
+        "rm" => Command::Rm(one("a note")?),
+        "export" => Command::Export {
+            note: one("a note")?,
+            out,
+            pdf,
+        },
+        _ => return Err(format!("unknown command: {name}")),
+    };
+    Ok(Args {
+        vault,
+        json,
+        help,
+        command: Some(command),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Result<Args, String> {
+        parse(s.split_whitespace().map(String::from), |name| {
+            (name == "NOTES_VAULT").then(|| "/srv/notes".to_owned())
+        })
+    }
+
+    #[test]
+    fn parses_commands() {
+        let a = args("--json new --folder Travail --title Réunion").unwrap();
+        assert!(a.json);
+        assert_eq!(a.vault, PathBuf::from("/srv/notes"));
+        assert_eq!(
+            a.command,
+            Some(Command::New {
+                folder: "Travail".into(),
+                title: "Réunion".into()
+            })
+        );
+        assert_eq!(
+            args("search budget 2024").unwrap().command,
+            Some(Command::Search("budget 2024".into()))
+        );
+        assert_eq!(
+            args("tag #Projet").unwrap().command,
+            Some(Command::Tag(Some("projet".into())))
+        );
+        assert_eq!(
+            args("ls --vault ~/Notes").unwrap().vault,
+            PathBuf::from("~/Notes")
+        );
+        assert_eq!(
+            args("export Travail/a.md --pdf -o a.pdf").unwrap().command,
+            Some(Command::Export {
+                note: "Travail/a.md".into(),
+                out: Some("a.pdf".into()),
+                pdf: true
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        assert!(args("").is_err());
+        assert!(args("frobnicate").is_err());
+        assert!(args("new --folder Travail").is_err());
+        assert!(args("cat").is_err());
+        assert!(args("mv a.md").is_err());
+        assert!(args("ls a b").is_err());
+        assert!(args("--verbose ls").is_err());
+        assert!(args("--help").unwrap().help);
+    }
+}
@@ -0,0 +1,215 @@
+use args::Command;
+use notes_app::models::Note;
+use notes_app::settings::Settings;
+use notes_app::vault::{self, Vault};
+use notes_app::{crypt, export, pdf, render};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io::{self, ErrorKind, IsTerminal, Read, Write};
+use std::{env, fs};
+
+mod args;
+
+fn main() {
+    let args = match args::parse(env::args().skip(1), |name| env::var(name).ok()) {
+        Ok(args) if args.help => {
+            println!("{}", args::USAGE);
+            return;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("notes: {e}\n\n{}", args::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(&args) {
+        eprintln!("notes: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &args::Args) -> Result<(), Box<dyn Error>> {
+    if !args.vault.is_dir() {
+        return Err(format!("{} is not a directory", args.vault.display()).into());
+    }
+    let vault = Vault::new(&args.vault);
+    let Some(command) = &args.command else {
+        return Ok(());
+    };
+    // texte pour un humain, JSON pour les scripts
+    let (text, json) = match command {
+        Command::New { folder, title } => {
+            let mut body = String::new();
+            if !io::stdin().is_terminal() {
+                io::stdin().read_to_string(&mut body)?;
+            }
+            let note =
+                new_note(&vault, folder, title, &body).map_err(|e| format!("{folder}: {e}"))?;
+            let rel = vault.relative(&note.path);
+            (format!("{rel}\n"), summary(&vault, &note))
+        }
+        Command::Ls(None) => {
+            let (folders, skipped) = vault.load()?;
+            report_skipped(&vault, &skipped);
+            let text = folders.iter().map(|f| format!("{}\n", f.name)).collect();
+            let json = folders
+                .iter()
+                .map(|f| {
+                    json!({
+                        "name": f.name,
+                        "path": vault.relative(&f.path),
+                        "notes": f.notes.len(),
+                    })
+                })
+                .collect();
+            (text, json)
+        }
+        Command::Ls(Some(folder)) => {
+            let path = vault.resolve(folder)?;
+            if !path.is_dir() {
+                return Err(format!("no folder {folder}").into());
+            }
+            let (folder, skipped) = vault::load_folder(&path)?;
+            report_skipped(&vault, &skipped);
+            list(&vault, &folder.notes)
+        }
+        Command::Cat(rel) => {
+            let note = vault.read_note(rel).map_err(|e| format!("{rel}: {e}"))?;
+            let mut json = summary(&vault, &note);
+            json["body"] = note.body.clone().into();
+            json["tags"] = render::tags(&note.body).into_iter().collect();
+            (note.body, json)
+        }
+        Command::Search(query) => {
+            let hits = vault.search(query)?;
+            let mut text = String::new();
+            let mut json = Vec::new();
+            for hit in &hits {
+                let rel = vault.relative(&hit.note.path);
+                text.push_str(&format!("{rel}\t{}\n", hit.snippet));
+                let mut entry = summary(&vault, &hit.note);
+                entry["snippet"] = hit.snippet.clone().into();
+                json.push(entry);
+            }
+            (text, Value::Array(json))
+        }
+        Command::Tag(None) => {
+            let tags = vault.tags()?;
+            let text = tags
+                .iter()
+                .map(|(tag, notes)| format!("{tag}\t{}\n", notes.len()))
+                .collect();
+            let json = tags
+                .iter()
+                .map(|(tag, notes)| (tag.clone(), notes.len().into()))
+                .collect();
+            (text, Value::Object(json))
+        }
+        Command::Tag(Some(tag)) => {
+            let notes = vault.tags()?.remove(tag).unwrap_or_default();
+            list(&vault, &notes)
+        }
+        Command::Mv(rel, dest) => {
+            let note = vault
+                .move_note(rel, dest)
+                .map_err(|e| format!("{rel}: {e}"))?;
+            let to = vault.relative(&note.path);
+            (format!("{to}\n"), json!({ "from": rel, "to": to }))
+        }
+        Command::Rm(rel) => {
+            vault.delete_note(rel).map_err(|e| format!("{rel}: {e}"))?;
+            (String::new(), json!({ "deleted": rel }))
+        }
+        Command::Export {
+            note: rel,
+            out,
+            pdf,
+        } => {
+            let note = vault.read_note(rel).map_err(|e| format!("{rel}: {e}"))?;
+            if crypt::is_encrypted(&note.body) {
+                return Err(format!("{rel}: {}", crypt::locked()).into());
+            }
+            let bytes = if *pdf {
+                pdf::note_pdf(&note)?
+            } else {
+                export::note_html(&note, &export::Theme::light()).into_bytes()
+            };
+            let Some(out) = out else {
+                return write_stdout(&bytes);
+            };
+            fs::write(out, bytes).map_err(|e| format!("{}: {e}", out.display()))?;
+            (
+                String::new(),
+                json!({ "path": vault.relative(&note.path), "out": out }),
+            )
+        }
+    };
+    if args.json {
+        let mut json = serde_json::to_string_pretty(&json)?;
+        json.push('\n');
+        write_stdout(json.as_bytes())
+    } else {
+        write_stdout(text.as_bytes())
+    }
+}
+
+/// Creates `folder/<title>.md`. Refused in an encrypted folder: the note
+/// would be written in clear, and the command line never has the key.
+fn new_note(vault: &Vault, folder: &str, title: &str, body: &str) -> io::Result<Note> {
+    if Settings::load(vault)?.encrypted_folders.contains(folder) {
+        return Err(crypt::locked());
+    }
+    vault.create_note(folder, title, body)
+}
+
+fn summary(vault: &Vault, note: &Note) -> Value {
+    json!({ "path": vault.relative(&note.path), "title": note.title })
+}
+
+/// One note path per line, or an array of `{"path", "title"}`.
+fn list(vault: &Vault, notes: &[Note]) -> (String, Value) {
+    let text = notes
+        .iter()
+        .map(|n| format!("{}\n", vault.relative(&n.path)))
+        .collect();
+    (text, notes.iter().map(|n| summary(vault, n)).collect())
+}
+
+/// Writes to stdout; a closed pipe (`notes ls | head -1`) is not an error.
+/// Tells on stderr which notes could not be read, so that the listing
+/// itself stays usable by scripts.
+fn report_skipped(vault: &Vault, skipped: &vault::Skipped) {
+    for (path, e) in skipped {
+        eprintln!("notes: skipped {}: {e}", vault.relative(path));
+    }
+}
+
+fn write_stdout(bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    match io::stdout().lock().write_all(bytes) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_new_notes_in_encrypted_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        fs::create_dir_all(dir.path().join("Secret")).unwrap();
+        fs::create_dir_all(dir.path().join("Travail")).unwrap();
+        let mut settings = Settings::default();
+        settings.encrypted_folders.insert("Secret".into());
+        settings.save(&vault).unwrap();
+
+        let err = new_note(&vault, "Secret", "Codes", "wifi : hunter2\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(!dir.path().join("Secret/Codes.md").exists());
+        assert!(new_note(&vault, "Travail", "Idées", "").is_ok());
+    }
+}
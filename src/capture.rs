@@ -0,0 +1,140 @@
+//! `notes_app --capture`: a small window that appends a few lines to the note
+//! of the day (or the inbox), then closes. Meant to be bound to a global
+//! shortcut of the desktop.
+
+use crate::app::VAULT_KEY;
+use crate::APP_NAME;
+use chrono::Local;
+use eframe::{egui, NativeOptions};
+use egui::{Color32, Key, TextEdit, ViewportCommand};
+use notes_app::journal;
+use notes_app::settings::Settings;
+use notes_app::vault::Vault;
+use std::env;
+use std::path::PathBuf;
+
+pub fn run() -> eframe::Result<()> {
+    let native = NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Capture")
+            .with_inner_size(egui::vec2(420.0, 170.0))
+            .with_always_on_top(),
+        // la fenêtre principale garde sa taille et sa position
+        persist_window: false,
+        ..Default::default()
+    };
+    eframe::run_native(
+        APP_NAME,
+        native,
+        Box::new(|cc| Ok(Box::new(Capture::new(cc)))),
+    )
+}
+
+struct Capture {
+    vault: Option<Vault>,
+    settings: Settings,
+    text: String,
+    error: Option<String>,
+}
+
+impl Capture {
+    fn new(cc: &eframe::CreationContext) -> Self {
+        // NOTES_VAULT, sinon le dossier ouvert en dernier dans l'application
+        let root = env::var_os("NOTES_VAULT").map(PathBuf::from).or_else(|| {
+            cc.storage
+                .and_then(|s| eframe::get_value::<PathBuf>(s, VAULT_KEY))
+        });
+        let vault = root.filter(|p| p.is_dir()).map(Vault::new);
+        let mut error = None;
+        let settings = match &vault {
+            Some(vault) => Settings::load(vault).unwrap_or_else(|e| {
+                error = Some(format!("Paramètres ignorés : {e}"));
+                Settings::default()
+            }),
+            None => {
+                error = Some(
+                    "Aucun dossier de notes : ouvrez-en un dans l’application ou définissez NOTES_VAULT."
+                        .into(),
+                );
+                Settings::default()
+            }
+        };
+        Self {
+            vault,
+            settings,
+            text: String::new(),
+            error,
+        }
+    }
+
+    /// Vault path of the note the text goes to.
+    fn target(&self) -> String {
+        match &self.settings.inbox_folder {
+            Some(folder) => format!("{folder}/Inbox.md"),
+            None => journal::daily_path(&self.settings, journal::today()),
+        }
+    }
+
+    fn save(&mut self, ctx: &egui::Context) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        match journal::capture(
+            vault,
+            &self.settings,
+            &self.text,
+            Local::now().naive_local(),
+        ) {
+            Ok(_) => ctx.send_viewport_cmd(ViewportCommand::Close),
+            Err(e) => self.error = Some(format!("Enregistrement impossible : {e}")),
+        }
+    }
+}
+
+impl eframe::App for Capture {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let (submit, cancel) = ctx.input(|i| {
+            (
+                i.modifiers.command && i.key_pressed(Key::Enter),
+                i.key_pressed(Key::Escape),
+            )
+        });
+        if cancel {
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+        let mut clicked = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("→ {}", self.target()));
+            ui.add(
+                TextEdit::multiline(&mut self.text)
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Note rapide…"),
+            )
+            .request_focus();
+            ui.horizontal(|ui| {
+                clicked = ui
+                    .add_enabled(self.vault.is_some(), egui::Button::new("Ajouter"))
+                    .clicked();
+                ui.label(
+                    egui::RichText::new("Ctrl+Entrée pour ajouter, Échap pour annuler")
+                        .color(Color32::GRAY)
+                        .small(),
+                );
+            });
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+
+        if submit || clicked {
+            self.save(ctx);
+        }
+    }
+
+    // la mémoire d'egui (tailles des panneaux…) reste celle de l'application
+    fn persist_egui_memory(&self) -> bool {
+        false
+    }
+}
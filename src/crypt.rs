@@ -0,0 +1,213 @@
+//! Encrypted notes. The file of an encrypted note holds an armored
+//! XChaCha20-Poly1305 ciphertext, so that everything reading the vault from
+//! disk (search, tags, sidecars, the web editor, git, `.history`) only ever
+//! sees the armor. The key is derived once per session from a passphrase
+//! with Argon2id; its salt and costs live in `.notes/key.json`.
+
+use crate::vault::Vault;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+pub const BEGIN: &str = "-----BEGIN ENCRYPTED NOTE-----";
+pub const END: &str = "-----END ENCRYPTED NOTE-----";
+/// What the tools that cannot decrypt show instead of an encrypted note.
+pub const PLACEHOLDER: &str = "*Note chiffrée.*\n";
+
+/// Argon2id costs of a new key: 64 MiB, 3 passes, 1 lane.
+const COSTS: Costs = Costs {
+    memory: 64 * 1024,
+    passes: 3,
+    lanes: 1,
+};
+/// Random nonce of each ciphertext, long enough never to repeat.
+const NONCE_LEN: usize = 24;
+/// Encrypted in the key file to recognise a wrong passphrase.
+const CHECK: &str = "notes";
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Costs {
+    /// In KiB.
+    memory: u32,
+    passes: u32,
+    lanes: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    salt: String,
+    costs: Costs,
+    check: String,
+}
+
+/// Key of the encrypted notes of a vault.
+pub struct Key(XChaCha20Poly1305);
+
+pub fn is_encrypted(text: &str) -> bool {
+    text.starts_with(BEGIN)
+}
+
+/// Whether a passphrase was already chosen for `vault`.
+pub fn has_key(vault: &Vault) -> bool {
+    key_file(vault).is_file()
+}
+
+fn key_file(vault: &Vault) -> PathBuf {
+    vault.config_dir().join("key.json")
+}
+
+impl Key {
+    /// The key of `vault`. The first call chooses the passphrase; later ones
+    /// fail with `PermissionDenied` when it does not match.
+    pub fn unlock(vault: &Vault, passphrase: &str) -> io::Result<Self> {
+        Self::unlock_with(vault, passphrase, COSTS)
+    }
+
+    fn unlock_with(vault: &Vault, passphrase: &str, costs: Costs) -> io::Result<Self> {
+        if passphrase.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "empty passphrase"));
+        }
+        let file = match fs::read_to_string(key_file(vault)) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let mut salt = [0u8; 16];
+                random(&mut salt)?;
+                let key = Self::derive(passphrase, &salt, costs)?;
+                let file = KeyFile {
+                    salt: BASE64.encode(salt),
+                    costs,
+                    check: key.seal(CHECK)?,
+                };
+                fs::create_dir_all(vault.config_dir())?;
+                fs::write(key_file(vault), serde_json::to_string_pretty(&file)?)?;
+                return Ok(key);
+            }
+            Err(e) => return Err(e),
+        };
+        let KeyFile { salt, costs, check } = file;
+        let salt = BASE64
+            .decode(salt)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        let key = Self::derive(passphrase, &salt, costs)?;
+        match key.open(&check) {
+            Ok(text) if text == CHECK => Ok(key),
+            _ => Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                "wrong passphrase",
+            )),
+        }
+    }
+
+    fn derive(passphrase: &str, salt: &[u8], costs: Costs) -> io::Result<Self> {
+        let params = Params::new(costs.memory, costs.passes, costs.lanes, None)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut *bytes)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Self(XChaCha20Poly1305::new((&*bytes).into())))
+    }
+
+    /// Encrypts `text` with a fresh random nonce, as armored text.
+    pub fn seal(&self, text: &str) -> io::Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        random(&mut nonce)?;
+        let data = self
+            .0
+            .encrypt(XNonce::from_slice(&nonce), text.as_bytes())
+            .map_err(|_| io::Error::other("encryption failed"))?;
+        let mut encoded = BASE64.encode([&nonce[..], &data].concat());
+        let mut armor = format!("{BEGIN}\n");
+        while !encoded.is_empty() {
+            let rest = encoded.split_off(encoded.len().min(64));
+            armor.push_str(&encoded);
+            armor.push('\n');
+            encoded = rest;
+        }
+        armor.push_str(END);
+        armor.push('\n');
+        Ok(armor)
+    }
+
+    /// Decrypts armored text; `InvalidData` if it was altered or encrypted
+    /// with another key.
+    pub fn open(&self, armor: &str) -> io::Result<String> {
+        let invalid =
+            || io::Error::new(ErrorKind::InvalidData, "not a note encrypted with this key");
+        let inner = armor
+            .trim()
+            .strip_prefix(BEGIN)
+            .and_then(|s| s.strip_suffix(END))
+            .ok_or_else(invalid)?;
+        let encoded: String = inner.split_whitespace().collect();
+        let data = BASE64.decode(encoded).map_err(|_| invalid())?;
+        if data.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, sealed) = data.split_at(NONCE_LEN);
+        let text = Zeroizing::new(
+            self.0
+                .decrypt(XNonce::from_slice(nonce), sealed)
+                .map_err(|_| invalid())?,
+        );
+        String::from_utf8(text.to_vec()).map_err(|_| invalid())
+    }
+}
+
+/// Error for what cannot be done without the key.
+pub fn locked() -> io::Error {
+    io::Error::new(
+        ErrorKind::PermissionDenied,
+        "encrypted note, unlock it first",
+    )
+}
+
+fn random(bytes: &mut [u8]) -> io::Result<()> {
+    getrandom::getrandom(bytes).map_err(|e| io::Error::other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap costs, for the tests.
+    const FAST: Costs = Costs {
+        memory: 8,
+        passes: 1,
+        lanes: 1,
+    };
+
+    #[test]
+    fn seals_and_opens_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        assert!(!has_key(&vault));
+        let key = Key::unlock_with(&vault, "correct horse", FAST).unwrap();
+        assert!(has_key(&vault));
+
+        let armor = key.seal("# Codes\n\nwifi : hunter2\n").unwrap();
+        assert!(is_encrypted(&armor));
+        assert!(!armor.contains("hunter2"));
+        assert_ne!(armor, key.seal("# Codes\n\nwifi : hunter2\n").unwrap());
+        assert_eq!(key.open(&armor).unwrap(), "# Codes\n\nwifi : hunter2\n");
+
+        let again = Key::unlock_with(&vault, "correct horse", FAST).unwrap();
+        assert_eq!(again.open(&armor).unwrap(), "# Codes\n\nwifi : hunter2\n");
+        let err = Key::unlock_with(&vault, "wrong", FAST).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+        let mut lines: Vec<String> = armor.lines().map(str::to_owned).collect();
+        let flipped = if lines[1].starts_with('A') { "B" } else { "A" };
+        lines[1].replace_range(..1, flipped);
+        assert!(key.open(&lines.join("\n")).is_err());
+        assert!(key.open("# pas chiffrée").is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::crypt;
+use crate::models::{Color, Folder, Note};
+use crate::render;
+use crate::url::percent_encode;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pulldown_cmark::{escape::escape_html, html, CodeBlockKind, CowStr, Event, Tag};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+/// Colours of the exported pages, taken from the app's theme.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub dark: bool,
+    pub background: Color,
+    pub text: Color,
+    pub strong: Color,
+    pub link: Color,
+    pub code_background: Color,
+    pub border: Color,
+}
+
+impl Theme {
+    /// The colours of the app's default light theme.
+    pub fn light() -> Self {
+        Self {
+            dark: false,
+            background: Color::rgb(248, 248, 248),
+            text: Color::rgb(80, 80, 80),
+            strong: Color::rgb(0, 0, 0),
+            link: Color::rgb(0, 155, 255),
+            code_background: Color::rgb(230, 230, 230),
+            border: Color::rgb(190, 190, 190),
+        }
+    }
+
+    /// The colours of the app's default dark theme.
+    pub fn dark() -> Self {
+        Self {
+            dark: true,
+            background: Color::rgb(27, 27, 27),
+            text: Color::rgb(140, 140, 140),
+            strong: Color::rgb(255, 255, 255),
+            link: Color::rgb(90, 170, 255),
+            code_background: Color::rgb(64, 64, 64),
+            border: Color::rgb(60, 60, 60),
+        }
+    }
+}
+
+/// Renders a note as a standalone HTML page: CSS derived from `theme`,
+/// images inlined as data URIs and highlighted code blocks.
+pub fn note_html(note: &Note, theme: &Theme) -> String {
+    let base = note.path.parent();
+    page(
+        &note.title,
+        theme,
+        &render_body(&note.body, base, theme.dark),
+    )
+}
+
+/// Writes `note` to `out` as a standalone HTML file. Refused for an
+/// encrypted note.
+pub fn export_note(note: &Note, out: &Path, theme: &Theme) -> io::Result<()> {
+    if crypt::is_encrypted(&note.body) {
+        return Err(crypt::locked());
+    }
+    fs::write(out, note_html(note, theme))
+}
+
+/// Exports every note of `folder` into `out_dir`, plus an `index.html`
+/// linking them all. Returns the path of the index page.
+pub fn export_folder(folder: &Folder, out_dir: &Path, theme: &Theme) -> io::Result<PathBuf> {
+    fs::create_dir_all(out_dir)?;
+    let mut list = String::from("<ul class=\"index\">\n");
+    for note in &folder.notes {
+        let file = html_file_name(note);
+        export_note(note, &out_dir.join(&file), theme)?;
+        list.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            percent_encode(&file),
+            escape(&note.title)
+        ));
+    }
+    list.push_str("</ul>\n");
+
+    let body = format!("<h1>{}</h1>\n{list}", escape(&folder.name));
+    let index = out_dir.join("index.html");
+    fs::write(&index, page(&folder.name, theme, &body))?;
+    Ok(index)
+}
+
+/// File name used for the exported page of `note` (`note_1.md` → `note_1.html`).
+pub fn html_file_name(note: &Note) -> String {
+    let stem = note
+        .path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| note.title.clone());
+    format!("{stem}.html")
+}
+
+fn render_body(md: &str, base: Option<&Path>, dark: bool) -> String {
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None; // (langage, contenu)
+
+    for ev in render::parser(md) {
+        match ev {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((lang, src)) = code.take() {
+                    events.push(Event::Html(highlight(&src, &lang, dark).into()));
+                }
+            }
+            Event::Text(t) if code.is_some() => {
+                if let Some((_, src)) = code.as_mut() {
+                    src.push_str(&t);
+                }
+            }
+            Event::Start(Tag::Image(kind, url, title)) => {
+                let url = inline_image(&url, base).map(CowStr::from).unwrap_or(url);
+                events.push(Event::Start(Tag::Image(kind, url, title)));
+            }
+            ev => events.push(ev),
+        }
+    }
+
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+/// Reads a local image and returns it as a `data:` URI. Remote URLs and
+/// unreadable files are left untouched (`None`).
+fn inline_image(url: &str, base: Option<&Path>) -> Option<String> {
+    if url.contains("://") || url.starts_with("data:") {
+        return None;
+    }
+    let path = Path::new(url);
+    let path = match base {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    let mime = match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => return None,
+    };
+    let bytes = fs::read(&path).ok()?;
+    Some(format!("data:{mime};base64,{}", STANDARD.encode(bytes)))
+}
+
+fn highlight(code: &str, lang: &str, dark: bool) -> String {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let ss = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let ts = THEMES.get_or_init(ThemeSet::load_defaults);
+
+    let syntax = ss
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+    let theme = &ts.themes[if dark {
+        "base16-ocean.dark"
+    } else {
+        "InspiredGitHub"
+    }];
+    highlighted_html_for_string(code, ss, syntax, theme)
+        .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>\n", escape(code)))
+}
+
+fn page(title: &str, theme: &Theme, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"UTF-8\" />\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape(title),
+        css(theme)
+    )
+}
+
+/// Feuille de style calquée sur les couleurs de l'application.
+fn css(t: &Theme) -> String {
+    format!(
+        r#"html, body {{ margin: 0; background: {bg}; color: {text}; }}
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, Oxygen,
+  Ubuntu, Cantarell, "Open Sans", "Helvetica Neue", sans-serif; line-height: 1.6; }}
+main {{ max-width: 46rem; margin: 0 auto; padding: 2rem 1.5rem; }}
+h1, h2, h3, h4, h5, h6, strong, th {{ color: {strong}; }}
+h1, h2 {{ margin: 1.2em 0 0.4em; }}
+a {{ color: {link}; }}
+code {{ background: {code_bg}; border-radius: 4px; padding: 0.1em 0.3em; }}
+pre {{ border-radius: 6px; padding: 1rem; overflow-x: auto; }}
+pre code {{ background: none; padding: 0; }}
+blockquote {{ border-left: 4px solid {border}; margin-left: 0; padding-left: 1rem; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid {border}; padding: 0.3em 0.6em; }}
+img {{ max-width: 100%; }}
+ul.index {{ list-style: none; padding: 0; }}
+ul.index li {{ padding: 0.3em 0; }}
+"#,
+        bg = t.background.hex(),
+        text = t.text.hex(),
+        strong = t.strong.hex(),
+        link = t.link.hex(),
+        code_bg = t.code_background.hex(),
+        border = t.border.hex(),
+    )
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    let _ = escape_html(&mut out, s);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_uses_note_title() {
+        let note = Note {
+            title: "Réunion <lundi>".into(),
+            body: "Bonjour".into(),
+            path: PathBuf::from("reunion.md"),
+        };
+        let html = note_html(&note, &Theme::dark());
+        assert!(html.contains("<title>Réunion &lt;lundi&gt;</title>"));
+        assert!(html.contains("<p>Bonjour</p>"));
+    }
+
+    #[test]
+    fn refuses_encrypted_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = Note {
+            title: "Secret".into(),
+            body: format!("{}\nAAAA\n{}\n", crypt::BEGIN, crypt::END),
+            path: PathBuf::from("secret.md"),
+        };
+        let out = dir.path().join("secret.html");
+        assert!(export_note(&note, &out, &Theme::dark()).is_err());
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn index_links_are_encoded() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = Note {
+            title: "Q&R <lundi>".into(),
+            body: String::new(),
+            path: PathBuf::from("a #1?%\"<b>.md"),
+        };
+        let mut folder = Folder::new("Dossier", Color::rgb(255, 0, 0), dir.path().into());
+        folder.notes.push(note);
+        let index = export_folder(&folder, &dir.path().join("site"), &Theme::dark()).unwrap();
+        let html = fs::read_to_string(index).unwrap();
+        let link = "<a href=\"a%20%231%3F%25%22%3Cb%3E.html\">Q&amp;R &lt;lundi&gt;</a>";
+        assert!(html.contains(link));
+    }
+
+    #[test]
+    fn code_blocks_are_highlighted() {
+        let html = render_body("```rust\nfn main() {}\n```\n", None, true);
+        assert!(html.contains("<pre style="));
+        assert!(!html.contains("<code class=\"language-rust\">"));
+    }
+
+    #[test]
+    fn remote_images_are_not_inlined() {
+        assert_eq!(inline_image("https://example.com/a.png", None), None);
+    }
+}
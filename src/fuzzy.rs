@@ -0,0 +1,79 @@
+//! Fuzzy matching of the command palette: the letters of the query must
+//! appear in order in the candidate, ignoring case and accents.
+
+/// Score of `candidate` for `query`, higher is better; `None` when it does
+/// not match. Consecutive letters and letters starting a word count more.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    let mut wanted = query.iter().peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut streak = 0;
+    for (i, c) in candidate.chars().enumerate() {
+        let Some(&&q) = wanted.peek() else {
+            break;
+        };
+        if fold(c) == q {
+            wanted.next();
+            streak += 1;
+            score += 1 + streak;
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 5;
+            }
+            // plus le début est proche, mieux c'est
+            if i == 0 {
+                score += 3;
+            }
+        } else {
+            streak = 0;
+        }
+        previous = Some(c);
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    // à score égal, le plus court
+    Some(score * 100 - candidate.chars().count() as i32)
+}
+
+/// Lower case without accent, for the letters of French.
+fn fold(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'à' | 'â' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'î' | 'ï' => 'i',
+        'ô' | 'ö' => 'o',
+        'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_word_starts_and_runs_first() {
+        assert!(score("xyz", "Paramètres").is_none());
+        assert!(score("", "Paramètres").is_some());
+        assert!(score("parametres", "Paramètres").is_some());
+        assert!(score("np", "Nouvelle note").is_none());
+
+        let mut found: Vec<&str> = ["Exporter la note en PDF", "Note du jour", "Nouvelle note"]
+            .into_iter()
+            .filter(|c| score("note", c).is_some())
+            .collect();
+        found.sort_by_key(|c| std::cmp::Reverse(score("note", c)));
+        assert_eq!(
+            found,
+            ["Note du jour", "Exporter la note en PDF", "Nouvelle note"]
+        );
+        assert!(score("jour", "Bonjour") < score("jour", "Note du jour"));
+    }
+}
@@ -0,0 +1,293 @@
+//! Optional git integration, through the `git` command: when the vault is (in)
+//! a git repository, its changes can be committed and pushed, and the log of
+//! a note browsed. The local history (`.history`) and the secrets of `.notes`
+//! (access tokens, key salt) are never committed.
+
+use crate::vault::Vault;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Pathspec of the vault without its local history and its secrets.
+const VAULT: [&str; 4] = [
+    ".",
+    ":(exclude).history",
+    ":(exclude).notes/editor_server.json",
+    ":(exclude).notes/key.json",
+];
+
+#[derive(Clone)]
+pub struct Repo {
+    /// The vault root, where every command runs.
+    dir: PathBuf,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Status {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// Files of the vault with uncommitted changes.
+    pub changed: Vec<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    /// Unix time, in seconds.
+    pub time: i64,
+    pub summary: String,
+}
+
+impl Repo {
+    /// The repository holding `vault`, if it is in one and git is installed.
+    pub fn open(vault: &Vault) -> Option<Self> {
+        let repo = Self {
+            dir: vault.root().to_path_buf(),
+        };
+        repo.git(&["rev-parse", "--is-inside-work-tree"])
+            .ok()
+            .filter(|out| out.trim() == "true")?;
+        Some(repo)
+    }
+
+    pub fn status(&self) -> io::Result<Status> {
+        let mut args = vec![
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--untracked-files=all",
+            "-z",
+            "--",
+        ];
+        args.extend(VAULT);
+        let out = self.git(&args)?;
+        let mut status = Status::default();
+        let mut entries = out.split('\0');
+        while let Some(entry) = entries.next() {
+            if let Some(header) = entry.strip_prefix("# ") {
+                match header.split_once(' ') {
+                    Some(("branch.head", head)) if head != "(detached)" => {
+                        status.branch = Some(head.to_owned());
+                    }
+                    Some(("branch.upstream", upstream)) => {
+                        status.upstream = Some(upstream.to_owned())
+                    }
+                    Some(("branch.ab", ab)) => {
+                        for n in ab.split(' ') {
+                            if let Some(n) = n.strip_prefix('+') {
+                                status.ahead = n.parse().unwrap_or(0);
+                            } else if let Some(n) = n.strip_prefix('-') {
+                                status.behind = n.parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            // le chemin est le dernier champ ; un renommage est suivi de l'ancien
+            let path = match entry.split(' ').next() {
+                Some("1") => entry.splitn(9, ' ').nth(8),
+                Some("2") => {
+                    entries.next();
+                    entry.splitn(10, ' ').nth(9)
+                }
+                Some("u") => entry.splitn(11, ' ').nth(10),
+                Some("?") => entry.get(2..),
+                _ => None,
+            };
+            status.changed.extend(path.map(str::to_owned));
+        }
+        Ok(status)
+    }
+
+    /// Commits every change of the vault with a generated message. Returns
+    /// `false` when there was nothing to commit.
+    pub fn commit_all(&self) -> io::Result<bool> {
+        let changed = self.status()?.changed;
+        if changed.is_empty() {
+            return Ok(false);
+        }
+        let mut args = vec!["add", "--all", "--"];
+        args.extend(VAULT);
+        self.git(&args)?;
+        self.git(&["commit", "--quiet", "--message", &commit_message(&changed)])
+            .map(|_| true)
+    }
+
+    /// Pushes the current branch, to `origin` when it has no upstream yet.
+    pub fn push(&self) -> io::Result<()> {
+        if self.status()?.upstream.is_some() {
+            self.git(&["push", "--quiet"])?;
+        } else {
+            self.git(&["push", "--quiet", "--set-upstream", "origin", "HEAD"])?;
+        }
+        Ok(())
+    }
+
+    /// Commits that changed the note `rel` (vault-relative), newest first.
+    pub fn log(&self, rel: &str) -> io::Result<Vec<Commit>> {
+        let out = self.git(&["log", "--follow", "--format=%H%x09%at%x09%s", "--", rel])?;
+        Ok(out
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(Commit {
+                    hash: fields.next()?.to_owned(),
+                    time: fields.next()?.parse().ok()?,
+                    summary: fields.next().unwrap_or_default().to_owned(),
+                })
+            })
+            .collect())
+    }
+
+    /// Text of the note `rel` in `commit`.
+    pub fn show(&self, commit: &Commit, rel: &str) -> io::Result<String> {
+        self.git(&["show", &format!("{}:./{rel}", commit.hash)])
+    }
+
+    fn git(&self, args: &[&str]) -> io::Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(format!(
+                "git {}: {}",
+                args[0],
+                stderr.trim()
+            )));
+        }
+        String::from_utf8(output.stdout).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// `Mise à jour de Travail/a.md`, or of several notes.
+pub fn commit_message(changed: &[String]) -> String {
+    match changed {
+        [one] => format!("Mise à jour de {one}"),
+        [first, second] => format!("Mise à jour de {first} et {second}"),
+        _ => {
+            let names: Vec<&str> = changed
+                .iter()
+                .take(3)
+                .map(|p| {
+                    Path::new(p)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or(p)
+                })
+                .collect();
+            format!(
+                "Mise à jour de {} notes ({}…)",
+                changed.len(),
+                names.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn commits_logs_and_pushes_to_a_bare_repository() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let (root, remote) = (dir.path().join("notes"), dir.path().join("remote.git"));
+        fs::create_dir_all(root.join("Travail")).unwrap();
+        run(dir.path(), &["init", "--quiet", "--bare", "remote.git"]);
+        run(&root, &["init", "--quiet"]);
+        run(&root, &["config", "user.name", "Test"]);
+        run(&root, &["config", "user.email", "test@example.com"]);
+        run(
+            &root,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+
+        let vault = Vault::new(&root);
+        assert!(Repo::open(&Vault::new(dir.path())).is_none());
+        let repo = Repo::open(&vault).unwrap();
+        assert!(!repo.commit_all().unwrap());
+
+        vault.write_note("Travail/a.md", "un\n").unwrap();
+        fs::create_dir_all(root.join(".history")).unwrap();
+        fs::write(root.join(".history/x"), "").unwrap();
+        assert_eq!(repo.status().unwrap().changed, ["Travail/a.md"]);
+        assert!(repo.commit_all().unwrap());
+        vault.write_note("Travail/a.md", "deux\n").unwrap();
+        assert!(repo.commit_all().unwrap());
+
+        let log = repo.log("Travail/a.md").unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].summary, "Mise à jour de Travail/a.md");
+        assert_eq!(repo.show(&log[1], "Travail/a.md").unwrap(), "un\n");
+
+        repo.push().unwrap();
+        vault.write_note("Travail/b.md", "").unwrap();
+        repo.commit_all().unwrap();
+        let status = repo.status().unwrap();
+        assert!(status.changed.is_empty());
+        assert_eq!((status.ahead, status.behind), (1, 0));
+        assert!(status.upstream.is_some_and(|u| u.starts_with("origin/")));
+    }
+
+    #[test]
+    fn never_commits_the_secrets() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        run(dir.path(), &["init", "--quiet"]);
+        run(dir.path(), &["config", "user.name", "Test"]);
+        run(dir.path(), &["config", "user.email", "test@example.com"]);
+        let vault = Vault::new(dir.path());
+        fs::create_dir_all(vault.config_dir()).unwrap();
+        for name in ["editor_server.json", "key.json", "settings.json"] {
+            fs::write(vault.config_dir().join(name), "{}").unwrap();
+        }
+        vault.write_note("a.md", "").unwrap();
+
+        let repo = Repo::open(&vault).unwrap();
+        assert!(repo.commit_all().unwrap());
+        let tracked = repo.git(&["ls-files"]).unwrap();
+        assert_eq!(
+            tracked.lines().collect::<Vec<_>>(),
+            [".notes/settings.json", "a.md"]
+        );
+    }
+
+    #[test]
+    fn names_the_changed_notes() {
+        let changed: Vec<String> = ["A/a.md", "A/b.md", "B/c.md", "B/d.md"]
+            .map(String::from)
+            .into();
+        assert_eq!(
+            commit_message(&changed[..2]),
+            "Mise à jour de A/a.md et A/b.md"
+        );
+        assert_eq!(
+            commit_message(&changed),
+            "Mise à jour de 4 notes (a.md, b.md, c.md…)"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Local history of the notes, in `.history` at the root of the vault.
+//! Each content is stored once, under its SHA-1, in `objects/`; the log of a
+//! note, `log/<note path>.log`, lists its versions as `<unix time> <hash>`.
+
+use crate::vault::Vault;
+use chrono::{DateTime, Local};
+use sha1::{Digest, Sha1};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Version {
+    /// Unix time, in seconds.
+    pub time: i64,
+    pub hash: String,
+}
+
+impl Version {
+    pub fn local_time(&self) -> Option<DateTime<Local>> {
+        local_time(self.time)
+    }
+}
+
+/// A line of a diff, newline included.
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Same(&'a str),
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+pub struct History {
+    dir: PathBuf,
+}
+
+pub fn now() -> i64 {
+    Local::now().timestamp()
+}
+
+/// Local date and time of a Unix time in seconds.
+pub fn local_time(time: i64) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(time, 0).map(|t| t.with_timezone(&Local))
+}
+
+impl History {
+    pub fn new(vault: &Vault) -> Self {
+        Self {
+            dir: vault.root().join(".history"),
+        }
+    }
+
+    /// Records `body` as the latest version of the note `rel`, unless it
+    /// already is. Returns whether a version was added.
+    pub fn snapshot(&self, rel: &str, body: &str, time: i64) -> io::Result<bool> {
+        let hash = format!("{:x}", Sha1::digest(body.as_bytes()));
+        if self.versions(rel)?.first().is_some_and(|v| v.hash == hash) {
+            return Ok(false);
+        }
+        let object = self.object_path(&hash);
+        if !object.exists() {
+            fs::create_dir_all(object.parent().unwrap_or(&self.dir))?;
+            // écrit puis renomme : un objet est complet ou absent
+            let tmp = object.with_extension("tmp");
+            fs::write(&tmp, body)?;
+            fs::rename(&tmp, &object)?;
+        }
+        let log = self.log_path(rel)?;
+        fs::create_dir_all(log.parent().unwrap_or(&self.dir))?;
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(log)?;
+        writeln!(file, "{time} {hash}")?;
+        Ok(true)
+    }
+
+    /// Versions of the note `rel`, newest first.
+    pub fn versions(&self, rel: &str) -> io::Result<Vec<Version>> {
+        match fs::read_to_string(self.log_path(rel)?) {
+            Ok(text) => Ok(parse_log(&text).into_iter().rev().collect()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn read(&self, version: &Version) -> io::Result<String> {
+        fs::read_to_string(self.object_path(&version.hash))
+    }
+
+    /// Forgets the versions older than `max_age` seconds, except the latest
+    /// one of each note, then deletes the contents no log refers to. Returns
+    /// the number of contents deleted.
+    pub fn prune(&self, max_age: i64, now: i64) -> io::Result<usize> {
+        let mut kept = HashSet::new();
+        for log in files(&self.dir.join("log"))? {
+            let versions = parse_log(&fs::read_to_string(&log)?);
+            let latest = versions.len().saturating_sub(1);
+            let keep: Vec<&Version> = versions
+                .iter()
+                .enumerate()
+                .filter(|(i, v)| *i == latest || now - v.time <= max_age)
+                .map(|(_, v)| v)
+                .collect();
+            if keep.len() < versions.len() {
+                let text: String = keep
+                    .iter()
+                    .map(|v| format!("{} {}\n", v.time, v.hash))
+                    .collect();
+                fs::write(&log, text)?;
+            }
+            kept.extend(keep.into_iter().map(|v| v.hash.clone()));
+        }
+        self.collect_garbage(&kept)
+    }
+
+    /// Forgets every version of the note `rel`, for instance once it is
+    /// encrypted, and deletes the contents only it referred to.
+    pub fn forget(&self, rel: &str) -> io::Result<()> {
+        match fs::remove_file(self.log_path(rel)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut kept = HashSet::new();
+        for log in files(&self.dir.join("log"))? {
+            kept.extend(
+                parse_log(&fs::read_to_string(&log)?)
+                    .into_iter()
+                    .map(|v| v.hash),
+            );
+        }
+        self.collect_garbage(&kept).map(|_| ())
+    }
+
+    /// Deletes the contents not in `kept`; returns how many.
+    fn collect_garbage(&self, kept: &HashSet<String>) -> io::Result<usize> {
+        let mut removed = 0;
+        for object in files(&self.dir.join("objects"))? {
+            let hash = object
+                .strip_prefix(self.dir.join("objects"))
+                .map(|p| p.to_string_lossy().replace(['/', '\\'], ""))
+                .unwrap_or_default();
+            if !kept.contains(&hash) {
+                fs::remove_file(object)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        let (dir, file) = hash.split_at(2.min(hash.len()));
+        self.dir.join("objects").join(dir).join(file)
+    }
+
+    fn log_path(&self, rel: &str) -> io::Result<PathBuf> {
+        if rel.is_empty()
+            || !Path::new(rel)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid path: {rel}"),
+            ));
+        }
+        Ok(self.dir.join("log").join(format!("{rel}.log")))
+    }
+}
+
+/// Line diff from `old` to `new`.
+pub fn diff<'a>(old: &'a str, new: &'a str) -> Vec<Change<'a>> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|c| match c.tag() {
+            ChangeTag::Equal => Change::Same(c.value()),
+            ChangeTag::Insert => Change::Added(c.value()),
+            ChangeTag::Delete => Change::Removed(c.value()),
+        })
+        .collect()
+}
+
+/// Versions in the order of the log, oldest first; bad lines are skipped.
+fn parse_log(text: &str) -> Vec<Version> {
+    text.lines()
+        .filter_map(|line| {
+            let (time, hash) = line.split_once(' ')?;
+            Some(Version {
+                time: time.parse().ok()?,
+                hash: hash.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Every file under `dir`, which may not exist.
+fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            found.extend(files(&path)?);
+        } else {
+            found.push(path);
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_deduplicated_versions_and_prunes_old_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        let history = History::new(&vault);
+        const DAY: i64 = 86_400;
+
+        assert!(history.snapshot("Travail/a.md", "un", 0).unwrap());
+        assert!(!history.snapshot("Travail/a.md", "un", DAY).unwrap());
+        assert!(history.snapshot("Travail/a.md", "deux", 2 * DAY).unwrap());
+        assert!(history.snapshot("Perso/b.md", "un", 3 * DAY).unwrap());
+        assert!(history.snapshot("Travail/a.md", "trois", 4 * DAY).unwrap());
+        assert!(history.snapshot("../a.md", "x", 0).is_err());
+
+        let versions = history.versions("Travail/a.md").unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.time).collect::<Vec<_>>(),
+            [4 * DAY, 2 * DAY, 0]
+        );
+        assert_eq!(history.read(&versions[1]).unwrap(), "deux");
+
+        // « deux » n'est plus référencé, « un » l'est encore par b.md
+        assert_eq!(history.prune(DAY, 5 * DAY).unwrap(), 1);
+        assert_eq!(history.versions("Travail/a.md").unwrap().len(), 1);
+        assert_eq!(
+            history
+                .read(&history.versions("Perso/b.md").unwrap()[0])
+                .unwrap(),
+            "un"
+        );
+        assert!(history.read(&versions[1]).is_err());
+
+        history.forget("Travail/a.md").unwrap();
+        assert!(history.versions("Travail/a.md").unwrap().is_empty());
+        assert!(history.read(&versions[0]).is_err());
+        assert_eq!(history.versions("Perso/b.md").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn diffs_lines() {
+        assert_eq!(
+            diff("a\nb\nc\n", "a\nc\nd\n"),
+            [
+                Change::Same("a\n"),
+                Change::Removed("b\n"),
+                Change::Same("c\n"),
+                Change::Added("d\n"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Daily notes: one note per day, named `YYYY-MM-DD.md`, in the journal
+//! folder of the vault settings.
+
+use crate::crypt::{self, Key};
+use crate::models::{Folder, Note};
+use crate::settings::Settings;
+use crate::templates::{self, Vars};
+use crate::vault::Vault;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use std::fs;
+use std::io::{self, ErrorKind};
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Vault path of the note of `date`.
+pub fn daily_path(settings: &Settings, date: NaiveDate) -> String {
+    format!("{}/{}.md", settings.journal_folder, date.format("%Y-%m-%d"))
+}
+
+/// Date of a daily note, from its file name.
+pub fn date_of(note: &Note) -> Option<NaiveDate> {
+    let stem = note.path.file_stem()?.to_str()?;
+    NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+}
+
+pub fn is_journal(settings: &Settings, folder: &Folder) -> bool {
+    folder.name == settings.journal_folder
+}
+
+/// Days of the notes of `folder`, sorted.
+pub fn days(folder: &Folder) -> Vec<NaiveDate> {
+    let mut days: Vec<NaiveDate> = folder.notes.iter().filter_map(date_of).collect();
+    days.sort();
+    days
+}
+
+/// The closest day before (`forward` false) or after `from` that has a note.
+pub fn neighbour(days: &[NaiveDate], from: NaiveDate, forward: bool) -> Option<NaiveDate> {
+    if forward {
+        days.iter().find(|d| **d > from).copied()
+    } else {
+        days.iter().rev().find(|d| **d < from).copied()
+    }
+}
+
+/// The note of `date`, created (with the journal folder) on first use from
+/// the template of the journal folder, `journal` by default. In an encrypted
+/// journal, creating the note needs `key`; the note returned stays encrypted.
+pub fn daily_note(
+    vault: &Vault,
+    settings: &Settings,
+    date: NaiveDate,
+    key: Option<&Key>,
+) -> io::Result<Note> {
+    let rel = daily_path(settings, date);
+    if let Ok(note) = vault.read_note(&rel) {
+        return Ok(note);
+    }
+    let day = date.format("%Y-%m-%d").to_string();
+    let folder = &settings.journal_folder;
+    let name = settings
+        .folder_templates
+        .get(folder)
+        .map_or("journal", String::as_str);
+    let body = match templates::load(vault, name)? {
+        Some(template) => {
+            let mut vars = Vars::now(&day, folder);
+            vars.date = date;
+            templates::expand(&template, &vars).0
+        }
+        None => format!("# {day}\n"),
+    };
+    let body = match (settings.encrypted_folders.contains(folder), key) {
+        (false, _) => body,
+        (true, Some(key)) => key.seal(&body)?,
+        (true, None) => return Err(crypt::locked()),
+    };
+    open_or_create(vault, &rel, &body)
+}
+
+/// Appends `text` under a timestamp to the inbox note, or to the note of the
+/// day when no inbox folder is set. Returns the updated note. Encrypted
+/// notes are refused: the capture window never holds the key.
+pub fn capture(
+    vault: &Vault,
+    settings: &Settings,
+    text: &str,
+    now: NaiveDateTime,
+) -> io::Result<Note> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "nothing to capture",
+        ));
+    }
+    let (note, stamp) = match &settings.inbox_folder {
+        Some(folder) if settings.encrypted_folders.contains(folder) => return Err(crypt::locked()),
+        Some(folder) => (
+            open_or_create(vault, &format!("{folder}/Inbox.md"), "# Inbox\n")?,
+            now.format("%Y-%m-%d %H:%M"),
+        ),
+        None => (
+            daily_note(vault, settings, now.date(), None)?,
+            now.format("%H:%M"),
+        ),
+    };
+    if crypt::is_encrypted(&note.body) {
+        return Err(crypt::locked());
+    }
+    let mut body = note.body;
+    // une ligne vide entre les entrées
+    while !body.is_empty() && !body.ends_with("\n\n") {
+        body.push('\n');
+    }
+    body.push_str(&format!("**{stamp}** {text}\n"));
+    vault.write_note(&vault.relative(&note.path), &body)
+}
+
+fn open_or_create(vault: &Vault, rel: &str, body: &str) -> io::Result<Note> {
+    match vault.read_note(rel) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            if let Some(dir) = vault.resolve(rel)?.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            vault.write_note(rel, body)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_into_the_daily_note_or_the_inbox() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        let mut settings = Settings::default();
+        let now = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(9, 5, 0)
+            .unwrap();
+
+        capture(&vault, &settings, "Appeler Marc\n", now).unwrap();
+        let note = capture(&vault, &settings, "Relire le devis", now).unwrap();
+        assert_eq!(vault.relative(&note.path), "Journal/2026-10-19.md");
+        assert_eq!(
+            note.body,
+            "# 2026-10-19\n\n**09:05** Appeler Marc\n\n**09:05** Relire le devis\n"
+        );
+
+        settings.inbox_folder = Some("Travail".into());
+        let note = capture(&vault, &settings, "Idée", now).unwrap();
+        assert_eq!(vault.relative(&note.path), "Travail/Inbox.md");
+        assert!(note.body.ends_with("\n\n**2026-10-19 09:05** Idée\n"));
+        assert!(capture(&vault, &settings, "  ", now).is_err());
+
+        // jamais de texte en clair dans un dossier chiffré
+        settings.encrypted_folders.insert("Travail".into());
+        let denied =
+            |r: io::Result<Note>| r.err().map(|e| e.kind()) == Some(ErrorKind::PermissionDenied);
+        assert!(denied(capture(&vault, &settings, "Idée", now)));
+        settings.inbox_folder = None;
+        settings.encrypted_folders.insert("Journal".into());
+        assert!(denied(daily_note(
+            &vault,
+            &settings,
+            now.date().succ_opt().unwrap(),
+            None
+        )));
+    }
+
+    #[test]
+    fn daily_notes_use_the_template_and_know_their_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        let settings = Settings::default();
+        let templates = vault.config_dir().join("templates");
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(
+            templates.join("journal.md"),
+            "# Journal du {{date}}\n\n## Tâches\n",
+        )
+        .unwrap();
+
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let note = daily_note(&vault, &settings, day(19), None).unwrap();
+        assert_eq!(note.body, "# Journal du 2026-10-19\n\n## Tâches\n");
+        daily_note(&vault, &settings, day(12), None).unwrap();
+        vault.create_note("Journal", "idées", "").unwrap();
+
+        let folder = vault.folders().unwrap().remove(0);
+        assert!(is_journal(&settings, &folder));
+        let days = days(&folder);
+        assert_eq!(days, vec![day(12), day(19)]);
+        assert_eq!(neighbour(&days, day(19), false), Some(day(12)));
+        assert_eq!(neighbour(&days, day(15), true), Some(day(19)));
+        assert_eq!(neighbour(&days, day(19), true), None);
+    }
+}
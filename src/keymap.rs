@@ -0,0 +1,283 @@
+//! Keyboard shortcuts of the app. Each action has default combos, which the
+//! `keymap` of the settings replaces action by action:
+//! `"keymap": { "search": ["Ctrl+F", "Ctrl+Shift+F"], "back": [] }`.
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    CommandPalette,
+    QuickOpen,
+    Search,
+    NewNote,
+    Back,
+    Forward,
+    Home,
+    Settings,
+    Today,
+    PreviousDay,
+    NextDay,
+    History,
+    ExportHtml,
+    ExportPdf,
+    ExportFolder,
+    Lock,
+    GitCommit,
+    GitPush,
+    CloseTab,
+    SplitView,
+    PreviewMode,
+}
+
+impl Action {
+    pub const ALL: [Action; 21] = [
+        Action::CommandPalette,
+        Action::QuickOpen,
+        Action::Search,
+        Action::NewNote,
+        Action::Back,
+        Action::Forward,
+        Action::Home,
+        Action::Settings,
+        Action::Today,
+        Action::PreviousDay,
+        Action::NextDay,
+        Action::History,
+        Action::ExportHtml,
+        Action::ExportPdf,
+        Action::ExportFolder,
+        Action::Lock,
+        Action::GitCommit,
+        Action::GitPush,
+        Action::CloseTab,
+        Action::SplitView,
+        Action::PreviewMode,
+    ];
+
+    /// Name in the keymap of the settings.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::CommandPalette => "command_palette",
+            Action::QuickOpen => "quick_open",
+            Action::Search => "search",
+            Action::NewNote => "new_note",
+            Action::Back => "back",
+            Action::Forward => "forward",
+            Action::Home => "home",
+            Action::Settings => "settings",
+            Action::Today => "today",
+            Action::PreviousDay => "previous_day",
+            Action::NextDay => "next_day",
+            Action::History => "history",
+            Action::ExportHtml => "export_html",
+            Action::ExportPdf => "export_pdf",
+            Action::ExportFolder => "export_folder",
+            Action::Lock => "lock",
+            Action::GitCommit => "git_commit",
+            Action::GitPush => "git_push",
+            Action::CloseTab => "close_tab",
+            Action::SplitView => "split_view",
+            Action::PreviewMode => "preview_mode",
+        }
+    }
+
+    /// Label in the command palette.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::CommandPalette => "Palette de commandes",
+            Action::QuickOpen => "Ouvrir une note",
+            Action::Search => "Rechercher dans les notes",
+            Action::NewNote => "Nouvelle note",
+            Action::Back => "Retour",
+            Action::Forward => "Avancer",
+            Action::Home => "Accueil",
+            Action::Settings => "Paramètres",
+            Action::Today => "Note du jour",
+            Action::PreviousDay => "Jour précédent",
+            Action::NextDay => "Jour suivant",
+            Action::History => "Historique de la note",
+            Action::ExportHtml => "Exporter la note en HTML",
+            Action::ExportPdf => "Exporter la note en PDF",
+            Action::ExportFolder => "Exporter le dossier en HTML",
+            Action::Lock => "Verrouiller les notes chiffrées",
+            Action::GitCommit => "Valider (git)",
+            Action::GitPush => "Pousser (git)",
+            Action::CloseTab => "Fermer l’onglet",
+            Action::SplitView => "Diviser ou réunir l’éditeur",
+            Action::PreviewMode => "Changer d’affichage (éditeur, aperçu…)",
+        }
+    }
+
+    fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Action::CommandPalette => &["Ctrl+Shift+P"],
+            Action::QuickOpen => &["Ctrl+P"],
+            Action::Search => &["Ctrl+F"],
+            Action::NewNote => &["Ctrl+N"],
+            Action::Back => &["Alt+Left"],
+            Action::Forward => &["Alt+Right"],
+            Action::Settings => &["Ctrl+,"],
+            Action::CloseTab => &["Ctrl+W"],
+            Action::SplitView => &["Ctrl+\\"],
+            Action::PreviewMode => &["Ctrl+E"],
+            _ => &[],
+        }
+    }
+}
+
+pub struct Keymap {
+    /// The most specific combos first: Ctrl+P would also match Ctrl+Shift+P.
+    bindings: Vec<(KeyboardShortcut, Action)>,
+}
+
+impl Keymap {
+    /// The default combos, with those of `overrides` (by action name) in
+    /// their place. Also returns what could not be understood.
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut bindings: Vec<(KeyboardShortcut, Action)> = Action::ALL
+            .iter()
+            .flat_map(|&action| {
+                action
+                    .defaults()
+                    .iter()
+                    .filter_map(move |combo| Some((parse(combo)?, action)))
+            })
+            .collect();
+        for (name, combos) in overrides {
+            let Some(action) = Action::ALL.into_iter().find(|a| a.name() == name) else {
+                errors.push(format!("unknown action {name}"));
+                continue;
+            };
+            bindings.retain(|(_, a)| *a != action);
+            for combo in combos {
+                match parse(combo) {
+                    Some(shortcut) => {
+                        // un raccourci ne fait qu'une chose
+                        bindings.retain(|(s, _)| *s != shortcut);
+                        bindings.push((shortcut, action));
+                    }
+                    None => errors.push(format!("invalid shortcut {combo} for {name}")),
+                }
+            }
+        }
+        bindings.sort_by_key(|(s, _)| {
+            let m = s.modifiers;
+            std::cmp::Reverse(
+                [m.alt, m.shift, m.command]
+                    .into_iter()
+                    .filter(|&b| b)
+                    .count(),
+            )
+        });
+        (Self { bindings }, errors)
+    }
+
+    pub fn bindings(&self) -> &[(KeyboardShortcut, Action)] {
+        &self.bindings
+    }
+
+    /// First combo of `action`, if it has one.
+    pub fn shortcut(&self, action: Action) -> Option<&KeyboardShortcut> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(s, _)| s)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).0
+    }
+}
+
+/// `Ctrl+Shift+P`, `Alt+Left`, `Ctrl+,`… Ctrl stands for Cmd on macOS.
+pub fn parse(combo: &str) -> Option<KeyboardShortcut> {
+    let (mods, key) = match combo.trim().rsplit_once('+') {
+        // « Ctrl++ »
+        Some((mods, "")) => (mods.strip_suffix('+')?, "+"),
+        Some((mods, key)) => (mods, key),
+        None => ("", combo.trim()),
+    };
+    let mut modifiers = Modifiers::NONE;
+    for part in mods.split('+').filter(|p| !p.is_empty()) {
+        modifiers = modifiers.plus(match part.trim().to_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => Modifiers::COMMAND,
+            "alt" | "option" => Modifiers::ALT,
+            "shift" | "maj" => Modifiers::SHIFT,
+            _ => return None,
+        });
+    }
+    // « left » comme « Left »
+    let key = key.trim();
+    let mut chars = key.chars();
+    let capitalized: String = chars
+        .next()
+        .into_iter()
+        .flat_map(char::to_uppercase)
+        .chain(chars)
+        .collect();
+    let key = Key::from_name(key).or_else(|| Key::from_name(&capitalized))?;
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_combos() {
+        let ctrl_shift = Modifiers::COMMAND.plus(Modifiers::SHIFT);
+        assert_eq!(
+            parse("Ctrl+Shift+P"),
+            Some(KeyboardShortcut::new(ctrl_shift, Key::P))
+        );
+        assert_eq!(
+            parse("alt+left"),
+            Some(KeyboardShortcut::new(Modifiers::ALT, Key::ArrowLeft))
+        );
+        assert_eq!(
+            parse("Ctrl+,"),
+            Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma))
+        );
+        assert_eq!(
+            parse("Ctrl++"),
+            Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Plus))
+        );
+        assert_eq!(
+            parse("F5"),
+            Some(KeyboardShortcut::new(Modifiers::NONE, Key::F5))
+        );
+        assert_eq!(parse("Hyper+P"), None);
+        assert_eq!(parse("Ctrl+Nope"), None);
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults_of_an_action() {
+        let overrides = BTreeMap::from([
+            (
+                "search".to_owned(),
+                vec!["Ctrl+Shift+F".to_owned(), "Ctrl+N".to_owned()],
+            ),
+            ("quick_open".to_owned(), vec![]),
+            ("fly".to_owned(), vec!["Ctrl+L".to_owned()]),
+            ("home".to_owned(), vec!["Ctrl+Hop".to_owned()]),
+        ]);
+        let (keymap, errors) = Keymap::new(&overrides);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(keymap.shortcut(Action::QuickOpen), None);
+        assert_eq!(keymap.shortcut(Action::NewNote), None);
+        assert_eq!(
+            keymap.shortcut(Action::Search),
+            parse("Ctrl+Shift+F").as_ref()
+        );
+        assert_eq!(keymap.shortcut(Action::Back), parse("Alt+Left").as_ref());
+        // les combinaisons à deux modificateurs passent en premier
+        assert_eq!(
+            keymap.bindings()[0].0.modifiers,
+            Modifiers::COMMAND.plus(Modifiers::SHIFT)
+        );
+    }
+}
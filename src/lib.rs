@@ -0,0 +1,17 @@
+//! Vault model shared by the desktop app and the command-line tools.
+
+pub mod crypt;
+pub mod export;
+pub mod fuzzy;
+pub mod git;
+pub mod history;
+pub mod journal;
+pub mod models;
+pub mod pdf;
+pub mod render;
+pub mod settings;
+pub mod templates;
+pub mod undo;
+pub mod url;
+pub mod vault;
+pub mod watch;
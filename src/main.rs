@@ -1,19 +1,66 @@
 use eframe::{egui, NativeOptions};
+use notes_app::pdf;
+use std::{env, fs, path::PathBuf};
 
 mod app;
+mod capture;
+mod keymap;
+mod nav;
+mod tabs;
 mod ui;
-mod models;
+
+/// Also names the storage shared by the main window and `--capture`.
+pub const APP_NAME: &str = "Notes App";
 
 fn main() -> eframe::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("export-pdf") {
+        export_pdf(&args[2..]);
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("--capture") {
+        return capture::run();
+    }
+
     let native = NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size(egui::vec2(800.0, 600.0)), // fenêtre plus grande
+        viewport: egui::ViewportBuilder::default().with_inner_size(egui::vec2(800.0, 600.0)), // fenêtre plus grande
         ..Default::default()
     };
 
     eframe::run_native(
-        "Notes App",
+        APP_NAME,
         native,
         Box::new(|cc| Ok(Box::new(app::NotesApp::new(cc)))),
     )
 }
+
+/// `notes_app export-pdf <note.md> [out.pdf]`
+fn export_pdf(args: &[String]) {
+    let Some(input) = args.first().map(PathBuf::from) else {
+        eprintln!("Usage: notes_app export-pdf <note.md> [out.pdf]");
+        std::process::exit(1);
+    };
+    let out = args
+        .get(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| input.with_extension("pdf"));
+    let note = match fs::read_to_string(&input) {
+        Ok(body) => notes_app::models::Note {
+            title: input
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            body,
+            path: input.clone(),
+        },
+        Err(e) => {
+            eprintln!("{}: {e}", input.display());
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = pdf::export_note(&note, &out) {
+        eprintln!("{}: {e}", input.display());
+        std::process::exit(1);
+    }
+    println!("Exported {}", out.display());
+}
@@ -1,7 +1,25 @@
-use serde::{Serialize, Deserialize};
-use egui::Color32;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+/// An sRGB colour, independent of any UI toolkit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// CSS notation, `#rrggbb`.
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Note {
     pub title: String,
@@ -12,13 +30,13 @@ pub struct Note {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Folder {
     pub name: String,
-    pub color: Color32,
+    pub color: Color,
     pub notes: Vec<Note>,
     pub path: PathBuf,
 }
 
 impl Folder {
-    pub fn new(name: &str, color: Color32, path: PathBuf) -> Self {
+    pub fn new(name: &str, color: Color, path: PathBuf) -> Self {
         Self {
             name: name.to_owned(),
             color,
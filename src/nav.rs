@@ -0,0 +1,165 @@
+//! Back and forward through the places viewed: notes, folders and home.
+
+use notes_app::models::Folder;
+use std::path::PathBuf;
+
+/// At most this many places to go back to.
+const MAX_PLACES: usize = 100;
+
+/// What the central panel shows. Paths rather than indices, which change
+/// when folders or notes are added.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Place {
+    pub folder: Option<PathBuf>,
+    pub note: Option<PathBuf>,
+    /// Scroll offset of the editor, restored on the way back.
+    pub scroll: f32,
+}
+
+impl Place {
+    /// The place of the folder `selected` of `folders` and of its note `note`.
+    pub fn of(folders: &[Folder], selected: Option<usize>, note: Option<usize>) -> Self {
+        let folder = selected.and_then(|f| folders.get(f));
+        Place {
+            folder: folder.map(|f| f.path.clone()),
+            note: folder
+                .zip(note)
+                .and_then(|(f, n)| f.notes.get(n))
+                .map(|n| n.path.clone()),
+            scroll: 0.0,
+        }
+    }
+
+    /// Indices of its folder and note in `folders`, of what is left of them.
+    pub fn locate(&self, folders: &[Folder]) -> (Option<usize>, Option<usize>) {
+        let note = self.note.as_ref().and_then(|path| {
+            folders.iter().enumerate().find_map(|(f, folder)| {
+                folder
+                    .notes
+                    .iter()
+                    .position(|n| &n.path == path)
+                    .map(|n| (f, n))
+            })
+        });
+        match note {
+            Some((f, n)) => (Some(f), Some(n)),
+            None => {
+                let folder = self
+                    .folder
+                    .as_ref()
+                    .and_then(|path| folders.iter().position(|f| &f.path == path));
+                (folder, None)
+            }
+        }
+    }
+
+    /// Same folder and note, wherever it was scrolled.
+    pub fn same(&self, other: &Place) -> bool {
+        self.folder == other.folder && self.note == other.note
+    }
+}
+
+#[derive(Default)]
+pub struct NavHistory {
+    back: Vec<Place>,
+    forward: Vec<Place>,
+}
+
+impl NavHistory {
+    /// Records `left` on the way to a new place; forward is forgotten.
+    pub fn visit(&mut self, left: Place) {
+        if self.back.last().is_some_and(|p| p.same(&left)) {
+            self.back.pop();
+        }
+        self.back.push(left);
+        if self.back.len() > MAX_PLACES {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// The previous place, `current` becoming the next one.
+    pub fn back(&mut self, current: Place) -> Option<Place> {
+        let place = self.back.pop()?;
+        self.forward.push(current);
+        Some(place)
+    }
+
+    /// The next place, `current` becoming the previous one.
+    pub fn forward(&mut self, current: Place) -> Option<Place> {
+        let place = self.forward.pop()?;
+        self.back.push(current);
+        Some(place)
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notes_app::models::Note;
+    use notes_app::vault::DEFAULT_FOLDER_COLOR;
+
+    fn note(name: &str, scroll: f32) -> Place {
+        Place {
+            folder: Some(PathBuf::from("A")),
+            note: Some(PathBuf::from(name)),
+            scroll,
+        }
+    }
+
+    fn folder(name: &str, notes: &[&str]) -> Folder {
+        let mut folder = Folder::new(name, DEFAULT_FOLDER_COLOR, PathBuf::from(name));
+        for title in notes {
+            let path = folder.path.join(format!("{title}.md"));
+            folder.notes.push(Note {
+                title: title.to_string(),
+                body: String::new(),
+                path,
+            });
+        }
+        folder
+    }
+
+    #[test]
+    fn goes_back_and_forward() {
+        let mut nav = NavHistory::default();
+        assert_eq!(nav.back(note("a", 0.0)), None);
+        nav.visit(Place::default());
+        nav.visit(note("a", 120.0));
+        nav.visit(note("b", 0.0));
+
+        assert_eq!(nav.back(note("c", 5.0)), Some(note("b", 0.0)));
+        assert_eq!(nav.back(note("b", 8.0)), Some(note("a", 120.0)));
+        assert_eq!(nav.forward(note("a", 120.0)), Some(note("b", 8.0)));
+        assert!(nav.can_go_forward());
+
+        // un nouveau lieu efface la suite
+        nav.visit(note("b", 8.0));
+        assert!(!nav.can_go_forward());
+        assert_eq!(nav.back(note("d", 0.0)), Some(note("b", 8.0)));
+        assert_eq!(nav.back(note("b", 8.0)), Some(note("a", 120.0)));
+        assert_eq!(nav.back(note("a", 120.0)), Some(Place::default()));
+        assert_eq!(nav.back(Place::default()), None);
+    }
+
+    #[test]
+    fn finds_its_place_again_after_a_folder_is_added() {
+        let mut folders = vec![folder("B", &["b1", "b2"]), folder("C", &[])];
+        let in_b = Place::of(&folders, Some(0), Some(1));
+        let in_c = Place::of(&folders, Some(1), None);
+        assert_eq!(in_b.note, Some(PathBuf::from("B/b2.md")));
+
+        folders.insert(0, folder("A", &["a1"]));
+        assert_eq!(in_b.locate(&folders), (Some(1), Some(1)));
+        assert_eq!(in_c.locate(&folders), (Some(2), None));
+
+        // dossier supprimé entre-temps
+        folders.remove(2);
+        assert_eq!(in_c.locate(&folders), (None, None));
+        assert_eq!(Place::of(&folders, Some(7), Some(0)), Place::default());
+    }
+}
@@ -0,0 +1,740 @@
+use crate::crypt;
+use crate::models::Note;
+use crate::render;
+use image::DynamicImage;
+use printpdf::path::PaintMode;
+use printpdf::{
+    BuiltinFont, Color, ImageTransform, IndirectFontRef, Line, Mm, PdfDocument,
+    PdfDocumentReference, PdfLayerIndex, PdfLayerReference, PdfPageIndex, Point, Rect, Rgb,
+};
+use pulldown_cmark::{Event, Tag};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// A4, en millimètres
+const PAGE_W: f32 = 210.0;
+const PAGE_H: f32 = 297.0;
+const MARGIN_X: f32 = 20.0;
+const MARGIN_TOP: f32 = 25.0;
+const MARGIN_BOTTOM: f32 = 22.0;
+const CONTENT_W: f32 = PAGE_W - 2.0 * MARGIN_X;
+
+const BODY_PT: f32 = 11.0;
+const CODE_PT: f32 = 9.0;
+const PT_TO_MM: f32 = 0.352_778;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Code,
+}
+
+impl Style {
+    fn from_flags(bold: bool, italic: bool) -> Self {
+        match (bold, italic) {
+            (true, true) => Style::BoldItalic,
+            (true, false) => Style::Bold,
+            (false, true) => Style::Italic,
+            (false, false) => Style::Regular,
+        }
+    }
+
+    fn bolder(self) -> Self {
+        match self {
+            Style::Regular => Style::Bold,
+            Style::Italic => Style::BoldItalic,
+            s => s,
+        }
+    }
+}
+
+struct Span {
+    text: String,
+    style: Style,
+}
+
+enum Block {
+    Heading(u8, Vec<Span>),
+    Paragraph(Vec<Span>),
+    Item {
+        depth: usize,
+        marker: String,
+        spans: Vec<Span>,
+    },
+    Quote(Vec<Span>),
+    Code(String),
+    /// Rows of cells; the first row is the table header.
+    Table(Vec<Vec<Vec<Span>>>),
+    Image(PathBuf),
+    Rule,
+}
+
+/// Renders a note to PDF bytes using the builtin PDF fonts, so no font
+/// file or external tool is needed. Refused for an encrypted note.
+pub fn note_pdf(note: &Note) -> io::Result<Vec<u8>> {
+    if crypt::is_encrypted(&note.body) {
+        return Err(crypt::locked());
+    }
+    layout(note)?.finish(&note.title)
+}
+
+/// Writes `note` to `out` as a PDF file.
+pub fn export_note(note: &Note, out: &Path) -> io::Result<()> {
+    fs::write(out, note_pdf(note)?)
+}
+
+fn layout(note: &Note) -> io::Result<Writer> {
+    let (doc, page, layer) = PdfDocument::new(&note.title, Mm(PAGE_W), Mm(PAGE_H), "contenu");
+    let mut w = Writer::new(doc, page, layer)?;
+    for block in blocks(&note.body, note.path.parent()) {
+        w.block(&block);
+    }
+    Ok(w)
+}
+
+/// The block being built while walking the event stream.
+#[derive(Default)]
+struct Builder {
+    out: Vec<Block>,
+    spans: Vec<Span>,
+    heading: Option<u8>,
+    quote: usize,
+    lists: Vec<Option<u64>>,
+    marker: Option<String>,
+}
+
+impl Builder {
+    /// Closes the current block, if it has any content.
+    fn flush(&mut self) {
+        if self.spans.iter().all(|s| s.text.trim().is_empty()) && self.marker.is_none() {
+            self.spans.clear();
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        let depth = self.lists.len();
+        self.out.push(if let Some(level) = self.heading {
+            Block::Heading(level, spans)
+        } else if depth > 0 {
+            Block::Item {
+                depth,
+                marker: self.marker.take().unwrap_or_default(),
+                spans,
+            }
+        } else if self.quote > 0 {
+            Block::Quote(spans)
+        } else {
+            Block::Paragraph(spans)
+        });
+    }
+}
+
+/// Flattens the Markdown event stream into layout blocks.
+fn blocks(md: &str, base: Option<&Path>) -> Vec<Block> {
+    let mut b = Builder::default();
+    let (mut bold, mut italic) = (0usize, 0usize);
+    let mut code: Option<String> = None;
+    let mut table: Option<Vec<Vec<Vec<Span>>>> = None;
+    let mut row: Vec<Vec<Span>> = Vec::new();
+    let mut in_image = false;
+
+    for ev in render::parser(md) {
+        let style = Style::from_flags(bold > 0, italic > 0);
+        match ev {
+            Event::Start(Tag::Heading(level, _, _)) => b.heading = Some(level as u8),
+            Event::End(Tag::Heading(..)) => {
+                b.flush();
+                b.heading = None;
+            }
+            Event::End(Tag::Paragraph) if table.is_none() => b.flush(),
+            Event::Start(Tag::BlockQuote) => b.quote += 1,
+            Event::End(Tag::BlockQuote) => {
+                b.flush();
+                b.quote = b.quote.saturating_sub(1);
+            }
+            Event::Start(Tag::CodeBlock(_)) => code = Some(String::new()),
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some(src) = code.take() {
+                    b.out.push(Block::Code(src));
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                b.flush();
+                b.lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                b.flush();
+                b.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                b.flush();
+                b.marker = Some(match b.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "•".to_owned(),
+                });
+            }
+            Event::End(Tag::Item) => b.flush(),
+            Event::Start(Tag::Emphasis) => italic += 1,
+            Event::End(Tag::Emphasis) => italic = italic.saturating_sub(1),
+            Event::Start(Tag::Strong) => bold += 1,
+            Event::End(Tag::Strong) => bold = bold.saturating_sub(1),
+            Event::Start(Tag::Table(_)) => table = Some(Vec::new()),
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => row.clear(),
+            Event::End(Tag::TableCell) => row.push(std::mem::take(&mut b.spans)),
+            Event::End(Tag::TableHead) | Event::End(Tag::TableRow) => {
+                if let Some(t) = table.as_mut() {
+                    t.push(std::mem::take(&mut row));
+                }
+            }
+            Event::End(Tag::Table(_)) => {
+                if let Some(t) = table.take() {
+                    b.out.push(Block::Table(t));
+                }
+            }
+            Event::Start(Tag::Image(_, url, _)) if !url.contains("://") => {
+                b.flush();
+                let path = Path::new(url.as_ref());
+                b.out.push(Block::Image(match base {
+                    Some(dir) if path.is_relative() => dir.join(path),
+                    _ => path.to_path_buf(),
+                }));
+                in_image = true;
+            }
+            Event::End(Tag::Image(..)) => in_image = false,
+            Event::Rule => {
+                b.flush();
+                b.out.push(Block::Rule);
+            }
+            Event::Text(t) => {
+                if let Some(src) = code.as_mut() {
+                    src.push_str(&t);
+                } else if !in_image {
+                    b.spans.push(Span {
+                        text: t.to_string(),
+                        style,
+                    });
+                }
+            }
+            Event::Code(t) => b.spans.push(Span {
+                text: t.to_string(),
+                style: Style::Code,
+            }),
+            Event::SoftBreak => b.spans.push(Span {
+                text: " ".into(),
+                style,
+            }),
+            Event::HardBreak => b.spans.push(Span {
+                text: "\n".into(),
+                style,
+            }),
+            Event::TaskListMarker(done) => b.spans.push(Span {
+                text: if done { "[x] " } else { "[ ] " }.into(),
+                style,
+            }),
+            Event::FootnoteReference(label) => b.spans.push(Span {
+                text: format!("[{label}]"),
+                style,
+            }),
+            _ => {}
+        }
+    }
+    b.flush();
+    b.out
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    italic: IndirectFontRef,
+    bold_italic: IndirectFontRef,
+    mono: IndirectFontRef,
+}
+
+impl Fonts {
+    fn get(&self, style: Style) -> &IndirectFontRef {
+        match style {
+            Style::Regular => &self.regular,
+            Style::Bold => &self.bold,
+            Style::Italic => &self.italic,
+            Style::BoldItalic => &self.bold_italic,
+            Style::Code => &self.mono,
+        }
+    }
+}
+
+/// Page cursor: `y` goes from the top margin down to the bottom margin,
+/// a new page is started whenever a block does not fit.
+struct Writer {
+    doc: PdfDocumentReference,
+    fonts: Fonts,
+    pages: Vec<(PdfPageIndex, PdfLayerIndex)>,
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+impl Writer {
+    fn new(
+        doc: PdfDocumentReference,
+        page: PdfPageIndex,
+        layer: PdfLayerIndex,
+    ) -> io::Result<Self> {
+        let font = |f| doc.add_builtin_font(f).map_err(io::Error::other);
+        let fonts = Fonts {
+            regular: font(BuiltinFont::Helvetica)?,
+            bold: font(BuiltinFont::HelveticaBold)?,
+            italic: font(BuiltinFont::HelveticaOblique)?,
+            bold_italic: font(BuiltinFont::HelveticaBoldOblique)?,
+            mono: font(BuiltinFont::Courier)?,
+        };
+        let current = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            fonts,
+            pages: vec![(page, layer)],
+            layer: current,
+            y: PAGE_H - MARGIN_TOP,
+        })
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_W), Mm(PAGE_H), "contenu");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.pages.push((page, layer));
+        self.y = PAGE_H - MARGIN_TOP;
+    }
+
+    /// Starts a new page if `h` millimetres do not fit on the current one.
+    fn ensure(&mut self, h: f32) {
+        if self.y - h < MARGIN_BOTTOM && self.y < PAGE_H - MARGIN_TOP {
+            self.new_page();
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Heading(level, spans) => {
+                let size = match level {
+                    1 => 20.0,
+                    2 => 16.0,
+                    3 => 14.0,
+                    4 => 12.5,
+                    _ => BODY_PT,
+                };
+                let spans: Vec<Span> = spans
+                    .iter()
+                    .map(|s| Span {
+                        text: s.text.clone(),
+                        style: s.style.bolder(),
+                    })
+                    .collect();
+                self.y -= size * PT_TO_MM * 0.6;
+                self.ensure(size * PT_TO_MM * 3.0);
+                self.paragraph(&spans, size, MARGIN_X, CONTENT_W);
+                self.y -= 2.0;
+            }
+            Block::Paragraph(spans) => {
+                self.paragraph(spans, BODY_PT, MARGIN_X, CONTENT_W);
+                self.y -= 2.5;
+            }
+            Block::Item {
+                depth,
+                marker,
+                spans,
+            } => {
+                let indent = MARGIN_X + 6.0 * *depth as f32;
+                let line_h = line_height(BODY_PT);
+                self.ensure(line_h);
+                if !marker.is_empty() {
+                    let w = text_width(marker, Style::Regular, BODY_PT);
+                    self.text(
+                        marker,
+                        Style::Regular,
+                        BODY_PT,
+                        indent - 1.5 - w,
+                        self.y - line_h * 0.8,
+                    );
+                }
+                self.paragraph(spans, BODY_PT, indent, PAGE_W - MARGIN_X - indent);
+                self.y -= 1.0;
+            }
+            Block::Quote(spans) => {
+                let top = self.y;
+                let spans: Vec<Span> = spans
+                    .iter()
+                    .map(|s| Span {
+                        text: s.text.clone(),
+                        style: if s.style == Style::Regular {
+                            Style::Italic
+                        } else {
+                            s.style
+                        },
+                    })
+                    .collect();
+                self.gray(0.4);
+                self.paragraph(&spans, BODY_PT, MARGIN_X + 6.0, CONTENT_W - 6.0);
+                self.gray(0.0);
+                if self.y < top {
+                    self.stroke(MARGIN_X + 2.0, top, MARGIN_X + 2.0, self.y, 0.8, 0.75);
+                }
+                self.y -= 2.5;
+            }
+            Block::Code(src) => self.code(src),
+            Block::Table(rows) => self.table(rows),
+            Block::Image(path) => self.image(path),
+            Block::Rule => {
+                self.ensure(6.0);
+                self.y -= 3.0;
+                self.stroke(MARGIN_X, self.y, PAGE_W - MARGIN_X, self.y, 0.5, 0.7);
+                self.y -= 3.0;
+            }
+        }
+    }
+
+    /// Lays out wrapped text starting at the cursor and moves the cursor below it.
+    fn paragraph(&mut self, spans: &[Span], size: f32, x: f32, width: f32) {
+        let line_h = line_height(size);
+        for line in wrap(spans, size, width) {
+            self.ensure(line_h);
+            self.y -= line_h;
+            let mut cx = x;
+            for (style, text) in &line {
+                self.text(text, *style, size, cx, self.y + line_h * 0.2);
+                cx += text_width(text, *style, size);
+            }
+        }
+    }
+
+    fn code(&mut self, src: &str) {
+        let line_h = line_height(CODE_PT);
+        let per_line =
+            ((CONTENT_W - 6.0) / char_width(' ', Style::Code, CODE_PT)).max(1.0) as usize;
+        self.y -= 1.0;
+        for raw in src.trim_end_matches('\n').split('\n') {
+            let chars: Vec<char> = raw.chars().collect();
+            let chunks: Vec<String> = if chars.is_empty() {
+                vec![String::new()]
+            } else {
+                chars.chunks(per_line).map(|c| c.iter().collect()).collect()
+            };
+            for chunk in chunks {
+                self.ensure(line_h);
+                self.gray(0.93);
+                self.layer.add_rect(
+                    Rect::new(
+                        Mm(MARGIN_X),
+                        Mm(self.y - line_h),
+                        Mm(PAGE_W - MARGIN_X),
+                        Mm(self.y),
+                    )
+                    .with_mode(PaintMode::Fill),
+                );
+                self.gray(0.0);
+                self.y -= line_h;
+                self.text(
+                    &chunk,
+                    Style::Code,
+                    CODE_PT,
+                    MARGIN_X + 3.0,
+                    self.y + line_h * 0.25,
+                );
+            }
+        }
+        self.y -= 3.5;
+    }
+
+    fn table(&mut self, rows: &[Vec<Vec<Span>>]) {
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if cols == 0 {
+            return;
+        }
+        let col_w = CONTENT_W / cols as f32;
+        let pad = 1.5;
+        let line_h = line_height(BODY_PT);
+        for (r, row) in rows.iter().enumerate() {
+            let cells: Vec<Vec<Vec<(Style, String)>>> = row
+                .iter()
+                .map(|cell| {
+                    let cell: Vec<Span> = cell
+                        .iter()
+                        .map(|s| Span {
+                            text: s.text.clone(),
+                            style: if r == 0 { s.style.bolder() } else { s.style },
+                        })
+                        .collect();
+                    wrap(&cell, BODY_PT, col_w - 2.0 * pad)
+                })
+                .collect();
+            let lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+            let row_h = lines as f32 * line_h + 2.0 * pad;
+            self.ensure(row_h);
+            let top = self.y;
+            for (c, cell) in cells.iter().enumerate() {
+                let x = MARGIN_X + c as f32 * col_w;
+                for (i, line) in cell.iter().enumerate() {
+                    let mut cx = x + pad;
+                    let base = top - pad - (i + 1) as f32 * line_h + line_h * 0.2;
+                    for (style, text) in line {
+                        self.text(text, *style, BODY_PT, cx, base);
+                        cx += text_width(text, *style, BODY_PT);
+                    }
+                }
+            }
+            let bottom = top - row_h;
+            self.stroke(MARGIN_X, top, PAGE_W - MARGIN_X, top, 0.3, 0.5);
+            self.stroke(MARGIN_X, bottom, PAGE_W - MARGIN_X, bottom, 0.3, 0.5);
+            for c in 0..=cols {
+                let x = MARGIN_X + c as f32 * col_w;
+                self.stroke(x, top, x, bottom, 0.3, 0.5);
+            }
+            self.y = bottom;
+        }
+        self.y -= 3.0;
+    }
+
+    fn image(&mut self, path: &Path) {
+        let Ok(img) = image::open(path) else {
+            self.paragraph(
+                &[Span {
+                    text: format!("[image introuvable : {}]", path.display()),
+                    style: Style::Italic,
+                }],
+                BODY_PT,
+                MARGIN_X,
+                CONTENT_W,
+            );
+            return;
+        };
+        let (px_w, px_h) = (img.width() as f32, img.height() as f32);
+        // 96 dpi par défaut, réduit pour tenir dans la page
+        let mut w = (px_w / 96.0 * 25.4).min(CONTENT_W);
+        let mut h = w * px_h / px_w;
+        let max_h = PAGE_H - MARGIN_TOP - MARGIN_BOTTOM;
+        if h > max_h {
+            w *= max_h / h;
+            h = max_h;
+        }
+        self.ensure(h);
+        self.y -= h;
+        let rgb = DynamicImage::ImageRgb8(img.to_rgb8());
+        printpdf::Image::from_dynamic_image(&rgb).add_to_layer(
+            self.layer.clone(),
+            ImageTransform {
+                translate_x: Some(Mm(MARGIN_X)),
+                translate_y: Some(Mm(self.y)),
+                dpi: Some(px_w * 25.4 / w),
+                ..Default::default()
+            },
+        );
+        self.y -= 3.0;
+    }
+
+    fn text(&self, text: &str, style: Style, size: f32, x: f32, y: f32) {
+        if !text.is_empty() {
+            self.layer
+                .use_text(text, size, Mm(x), Mm(y), self.fonts.get(style));
+        }
+    }
+
+    fn gray(&self, level: f32) {
+        self.layer
+            .set_fill_color(Color::Rgb(Rgb::new(level, level, level, None)));
+    }
+
+    fn stroke(&self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, level: f32) {
+        self.layer
+            .set_outline_color(Color::Rgb(Rgb::new(level, level, level, None)));
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(x1), Mm(y1)), false),
+                (Point::new(Mm(x2), Mm(y2)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    /// Draws the header (title) and footer (page number) on every page.
+    fn finish(self, title: &str) -> io::Result<Vec<u8>> {
+        let total = self.pages.len();
+        for (i, (page, layer)) in self.pages.iter().enumerate() {
+            let layer = self.doc.get_page(*page).get_layer(*layer);
+            layer.set_fill_color(Color::Rgb(Rgb::new(0.45, 0.45, 0.45, None)));
+            layer.use_text(
+                title,
+                8.0,
+                Mm(MARGIN_X),
+                Mm(PAGE_H - 14.0),
+                &self.fonts.regular,
+            );
+            layer.set_outline_color(Color::Rgb(Rgb::new(0.8, 0.8, 0.8, None)));
+            layer.set_outline_thickness(0.3);
+            layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(MARGIN_X), Mm(PAGE_H - 16.0)), false),
+                    (Point::new(Mm(PAGE_W - MARGIN_X), Mm(PAGE_H - 16.0)), false),
+                ],
+                is_closed: false,
+            });
+            let footer = format!("{} / {total}", i + 1);
+            let x = (PAGE_W - text_width(&footer, Style::Regular, 8.0)) / 2.0;
+            layer.use_text(footer, 8.0, Mm(x), Mm(12.0), &self.fonts.regular);
+        }
+        self.doc.save_to_bytes().map_err(io::Error::other)
+    }
+}
+
+fn line_height(size: f32) -> f32 {
+    size * PT_TO_MM * 1.35
+}
+
+/// Greedy word wrap; returns lines made of consecutive (style, text) runs.
+fn wrap(spans: &[Span], size: f32, width: f32) -> Vec<Vec<(Style, String)>> {
+    let mut lines: Vec<Vec<(Style, String)>> = Vec::new();
+    let mut line: Vec<(Style, String)> = Vec::new();
+    let mut line_w = 0.0;
+
+    let push = |line: &mut Vec<(Style, String)>, style: Style, word: &str| match line.last_mut() {
+        Some((s, text)) if *s == style => text.push_str(word),
+        _ => line.push((style, word.to_owned())),
+    };
+
+    for span in spans {
+        for (i, part) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(std::mem::take(&mut line));
+                line_w = 0.0;
+            }
+            for word in part.split_inclusive(' ') {
+                let w = text_width(word, span.style, size);
+                let visible = text_width(word.trim_end(), span.style, size);
+                if line_w + visible > width && line_w > 0.0 {
+                    lines.push(std::mem::take(&mut line));
+                    line_w = 0.0;
+                    if word.trim().is_empty() {
+                        continue;
+                    }
+                }
+                if visible > width {
+                    // mot plus long qu'une ligne : coupure au caractère
+                    for c in word.chars() {
+                        let cw = char_width(c, span.style, size);
+                        if line_w + cw > width && line_w > 0.0 {
+                            lines.push(std::mem::take(&mut line));
+                            line_w = 0.0;
+                        }
+                        push(&mut line, span.style, c.encode_utf8(&mut [0; 4]));
+                        line_w += cw;
+                    }
+                    continue;
+                }
+                push(&mut line, span.style, word);
+                line_w += w;
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn text_width(text: &str, style: Style, size: f32) -> f32 {
+    text.chars().map(|c| char_width(c, style, size)).sum()
+}
+
+/// Advance width in millimetres, from the Helvetica / Courier AFM metrics.
+fn char_width(c: char, style: Style, size: f32) -> f32 {
+    let units = match style {
+        Style::Code => 600.0,
+        Style::Bold | Style::BoldItalic => helvetica_width(c) * 1.07,
+        Style::Regular | Style::Italic => helvetica_width(c),
+    };
+    units / 1000.0 * size * PT_TO_MM
+}
+
+fn helvetica_width(c: char) -> f32 {
+    const ASCII: [u16; 95] = [
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+        278, // ' '..'/'
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584,
+        556, // '0'..'?'
+        1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722,
+        778, // '@'..'O'
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469,
+        556, // 'P'..'_'
+        333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556,
+        556, // '`'..'o'
+        556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // 'p'..'~'
+    ];
+    match c {
+        ' '..='~' => ASCII[c as usize - 32] as f32,
+        'é' | 'è' | 'ê' | 'ë' | 'à' | 'â' | 'ä' | 'ù' | 'û' | 'ü' | 'ô' | 'ö' => 556.0,
+        'î' | 'ï' | 'ì' | 'í' => 278.0,
+        'ç' => 500.0,
+        '•' => 350.0,
+        '–' => 556.0,
+        '—' | '…' => 1000.0,
+        _ => 600.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_long_paragraphs() {
+        let spans = [Span {
+            text: "mot ".repeat(200),
+            style: Style::Regular,
+        }];
+        let lines = wrap(&spans, BODY_PT, CONTENT_W);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            let w: f32 = line
+                .iter()
+                .map(|(s, t)| text_width(t.trim_end(), *s, BODY_PT))
+                .sum();
+            assert!(w <= CONTENT_W);
+        }
+    }
+
+    #[test]
+    fn long_notes_span_several_pages() {
+        let note = Note {
+            title: "Compte rendu".into(),
+            body: "# Réunion\n\n".to_owned() + &"- point à traiter\n".repeat(120),
+            path: PathBuf::from("cr.md"),
+        };
+        let writer = layout(&note).unwrap();
+        assert!(writer.pages.len() >= 2);
+        assert!(writer.finish(&note.title).unwrap().starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn refuses_encrypted_notes() {
+        let note = Note {
+            title: "Secret".into(),
+            body: format!("{}\nAAAA\n{}\n", crypt::BEGIN, crypt::END),
+            path: PathBuf::from("secret.md"),
+        };
+        let err = note_pdf(&note).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn markdown_is_split_into_blocks() {
+        let md = "# Titre\n\nTexte **gras**\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n```\ncode\n```\n";
+        let b = blocks(md, None);
+        assert!(matches!(b[0], Block::Heading(1, _)));
+        assert!(matches!(b[1], Block::Paragraph(_)));
+        assert!(matches!(&b[2], Block::Table(rows) if rows.len() == 2));
+        assert!(matches!(b[3], Block::Code(_)));
+    }
+}
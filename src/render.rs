@@ -0,0 +1,285 @@
+//! Markdown parsing shared by the desktop app, `md_watch` and `editor_server`,
+//! so that a note renders the same in all of them.
+
+use pulldown_cmark::{html, Event, HeadingLevel, Options, Parser, Tag};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Extensions enabled on top of CommonMark:
+///
+/// * tables (`| a | b |`)
+/// * footnotes (`texte[^1]` … `[^1]: note`)
+/// * strikethrough (`~~barré~~`)
+/// * task lists (`- [ ] à faire`, `- [x] fait`)
+///
+/// These are the extensions of the preview of the desktop app
+/// (egui_commonmark). Smart punctuation and heading attributes stay off: the
+/// rendered text is the text that was typed.
+pub fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+/// Parser over `md` with [`options`].
+pub fn parser(md: &str) -> Parser<'_, '_> {
+    Parser::new_ext(md, options())
+}
+
+/// Renders `md` as an HTML fragment.
+pub fn to_html(md: &str) -> String {
+    let mut out = String::new();
+    html::push_html(&mut out, parser(md));
+    out
+}
+
+/// Text of the first level-1 heading, used as a page title.
+pub fn first_heading(md: &str) -> Option<String> {
+    let mut in_h1 = false;
+    let mut title = String::new();
+    for ev in parser(md) {
+        match ev {
+            Event::Start(Tag::Heading(HeadingLevel::H1, ..)) => in_h1 = true,
+            Event::End(Tag::Heading(..)) if in_h1 => return Some(title),
+            Event::Text(t) | Event::Code(t) if in_h1 => title.push_str(&t),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Text events outside of code, with consecutive runs merged so that
+/// `[[wiki links]]` and `#tags` split by the parser are seen whole.
+pub fn text_runs(md: &str) -> Vec<Event<'_>> {
+    let mut out: Vec<Event> = Vec::new();
+    let mut in_code = false;
+    for ev in parser(md) {
+        match ev {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code = true;
+                out.push(ev);
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code = false;
+                out.push(ev);
+            }
+            Event::Text(t) if !in_code => match out.last_mut() {
+                Some(Event::Text(prev)) => *prev = format!("{prev}{t}").into(),
+                _ => out.push(Event::Text(t)),
+            },
+            ev => out.push(ev),
+        }
+    }
+    out
+}
+
+/// Lowercased `#tags` of a note, outside of code.
+pub fn tags(md: &str) -> BTreeSet<String> {
+    let mut tags = BTreeSet::new();
+    let mut in_code = false;
+    for ev in text_runs(md) {
+        match ev {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(Tag::CodeBlock(_)) => in_code = false,
+            Event::Text(t) if !in_code => {
+                for piece in split_inline(&t) {
+                    if let Inline::Tag(tag) = piece {
+                        tags.insert(tag.to_lowercase());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tags
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Inline<'a> {
+    Text(&'a str),
+    /// `[[target]]` or `[[target|label]]`
+    WikiLink(&'a str, &'a str),
+    Tag(&'a str),
+}
+
+/// Splits a text run into plain text, wiki-links and `#tags`.
+pub fn split_inline(text: &str) -> Vec<Inline<'_>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    let bytes = text.as_bytes();
+    while i < text.len() {
+        if text[i..].starts_with("[[") {
+            if let Some(end) = text[i + 2..].find("]]") {
+                let inner = &text[i + 2..i + 2 + end];
+                if start < i {
+                    out.push(Inline::Text(&text[start..i]));
+                }
+                let (target, label) = inner.split_once('|').unwrap_or((inner, inner));
+                out.push(Inline::WikiLink(target, label));
+                i += end + 4;
+                start = i;
+                continue;
+            }
+        }
+        let at_word_start = i == 0 || bytes[i - 1].is_ascii_whitespace() || bytes[i - 1] == b'(';
+        if bytes[i] == b'#' && at_word_start {
+            let rest = &text[i + 1..];
+            let len: usize = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                .map(char::len_utf8)
+                .sum();
+            if rest.chars().next().is_some_and(char::is_alphabetic) {
+                if start < i {
+                    out.push(Inline::Text(&text[start..i]));
+                }
+                out.push(Inline::Tag(&rest[..len]));
+                i += 1 + len;
+                start = i;
+                continue;
+            }
+        }
+        i += text[i..].chars().next().map_or(1, char::len_utf8);
+    }
+    if start < text.len() {
+        out.push(Inline::Text(&text[start..]));
+    }
+    out
+}
+
+/// Source ranges of the top-level blocks of `md` (headings, paragraphs,
+/// lists…), in order; blank lines between blocks belong to none.
+pub fn blocks(md: &str) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    for (ev, range) in parser(md).into_offset_iter() {
+        match ev {
+            Event::Start(_) => {
+                if depth == 0 {
+                    out.push(range);
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            // règles et blocs HTML n'ont pas de début ni de fin
+            _ if depth == 0 => out.push(range),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Scheme of the links [`link_wiki_links`] makes.
+pub const NOTE_SCHEME: &str = "note:";
+
+/// `md` with its `[[wiki links]]`, outside of code, written as Markdown links
+/// to `note:target`, for renderers that only know those.
+pub fn link_wiki_links(md: &str) -> Cow<'_, str> {
+    let code: Vec<Range<usize>> = parser(md)
+        .into_offset_iter()
+        .filter(|(ev, _)| matches!(ev, Event::Code(_) | Event::Start(Tag::CodeBlock(_))))
+        .map(|(_, range)| range)
+        .collect();
+    let mut out = String::new();
+    let mut done = 0;
+    let mut from = 0;
+    while let Some(at) = md[from..].find("[[").map(|i| from + i) {
+        let Some(end) = md[at + 2..].find("]]").map(|i| at + 2 + i) else {
+            break;
+        };
+        from = end + 2;
+        let inner = &md[at + 2..end];
+        if code.iter().any(|r| r.contains(&at)) || inner.contains(['\n', '<', '>', '[', ']']) {
+            continue;
+        }
+        let (target, label) = inner.split_once('|').unwrap_or((inner, inner));
+        out.push_str(&md[done..at]);
+        out.push_str(&format!(
+            "[{}](<{NOTE_SCHEME}{}>)",
+            label.trim(),
+            target.trim()
+        ));
+        done = from;
+    }
+    if done == 0 {
+        return Cow::Borrowed(md);
+    }
+    out.push_str(&md[done..]);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_documented_extensions() {
+        let html = to_html(
+            "| a | b |\n|---|---|\n| 1 | 2 |\n\n- [x] fait\n\n~~non~~ [^1]\n\n[^1]: note\n",
+        );
+        assert!(html.contains("<table>"));
+        assert!(html.contains("checkbox"));
+        assert!(html.contains("<del>non</del>"));
+        assert!(html.contains("footnote-definition"));
+        // pas de ponctuation typographique ni d'attributs de titre
+        assert_eq!(to_html("\"a\" -- b"), "<p>&quot;a&quot; -- b</p>\n");
+        assert!(to_html("# Titre {#id}").contains("Titre {#id}"));
+    }
+
+    #[test]
+    fn finds_the_first_level_one_heading() {
+        assert_eq!(
+            first_heading("## Sous-titre\n\n# Le `vrai` titre\n").as_deref(),
+            Some("Le vrai titre")
+        );
+        assert_eq!(first_heading("Pas de titre"), None);
+    }
+
+    #[test]
+    fn splits_wiki_links_and_tags() {
+        assert_eq!(
+            split_inline("voir [[Réunion|la réunion]] #projet-x, pas #1"),
+            vec![
+                Inline::Text("voir "),
+                Inline::WikiLink("Réunion", "la réunion"),
+                Inline::Text(" "),
+                Inline::Tag("projet-x"),
+                Inline::Text(", pas #1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_top_level_blocks() {
+        let md = "# Titre\n\nUn paragraphe\nsur deux lignes.\n\n- a\n- b\n\n---\n";
+        let blocks: Vec<&str> = blocks(md).into_iter().map(|r| md[r].trim_end()).collect();
+        assert_eq!(
+            blocks,
+            [
+                "# Titre",
+                "Un paragraphe\nsur deux lignes.",
+                "- a\n- b",
+                "---"
+            ]
+        );
+    }
+
+    #[test]
+    fn links_wiki_links_outside_of_code() {
+        let md = "voir [[Réunion|la réunion]] et [[Idées]], pas `[[code]]`";
+        assert_eq!(
+            link_wiki_links(md),
+            "voir [la réunion](<note:Réunion>) et [Idées](<note:Idées>), pas `[[code]]`"
+        );
+        assert!(matches!(link_wiki_links("rien [[ ici"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn tags_ignore_code() {
+        let md = "#urgent et `#pas_un_tag`\n\n```\n#toujours pas\n```\n";
+        assert_eq!(tags(md).into_iter().collect::<Vec<_>>(), vec!["urgent"]);
+    }
+}
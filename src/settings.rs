@@ -0,0 +1,92 @@
+//! Settings of a vault, stored with it so that every front end sees them.
+
+use crate::vault::Vault;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, ErrorKind};
+
+/// Stored in `.notes/settings.json` inside the vault.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Folder of the daily notes (`YYYY-MM-DD.md`).
+    pub journal_folder: String,
+    /// Quick capture appends to `Inbox.md` in this folder instead of the
+    /// note of the day.
+    pub inbox_folder: Option<String>,
+    /// Template of the new notes of a folder, by folder name.
+    pub folder_templates: BTreeMap<String, String>,
+    /// Commit the vault once edits have stopped, when it is a git repository.
+    pub git_auto_commit: bool,
+    /// Push after each automatic commit.
+    pub git_push: bool,
+    /// Folders whose new notes are encrypted.
+    pub encrypted_folders: BTreeSet<String>,
+    /// Encrypted notes lock again after this long without input; 0 never.
+    pub lock_after_minutes: u32,
+    /// Keep the undo steps of the notes between sessions, in `.history/undo`.
+    pub persistent_undo: bool,
+    /// Shortcuts replacing the default ones, by action (see `keymap` in the app).
+    pub keymap: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            journal_folder: "Journal".into(),
+            inbox_folder: None,
+            folder_templates: BTreeMap::new(),
+            git_auto_commit: false,
+            git_push: false,
+            encrypted_folders: BTreeSet::new(),
+            lock_after_minutes: 10,
+            persistent_undo: false,
+            keymap: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// The settings of `vault`, or the defaults when it has none.
+    pub fn load(vault: &Vault) -> io::Result<Self> {
+        match fs::read_to_string(vault.config_dir().join("settings.json")) {
+            Ok(text) => {
+                serde_json::from_str(&text).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, vault: &Vault) -> io::Result<()> {
+        fs::create_dir_all(vault.config_dir())?;
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(vault.config_dir().join("settings.json"), json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_take_their_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        assert_eq!(Settings::load(&vault).unwrap(), Settings::default());
+
+        fs::create_dir(vault.config_dir()).unwrap();
+        fs::write(
+            vault.config_dir().join("settings.json"),
+            r#"{"inbox_folder":"Boîte"}"#,
+        )
+        .unwrap();
+        let settings = Settings::load(&vault).unwrap();
+        assert_eq!(settings.journal_folder, "Journal");
+        assert_eq!(settings.inbox_folder.as_deref(), Some("Boîte"));
+
+        settings.save(&vault).unwrap();
+        assert_eq!(Settings::load(&vault).unwrap(), settings);
+    }
+}
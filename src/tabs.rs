@@ -0,0 +1,205 @@
+//! Notes open in the window: one pane, or two side by side, each with its
+//! own tabs. Tabs hold note paths, which do not move when notes are added.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Tabs of a pane.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pane {
+    pub tabs: Vec<PathBuf>,
+    pub active: usize,
+}
+
+impl Pane {
+    pub fn current(&self) -> Option<&Path> {
+        self.tabs.get(self.active).map(PathBuf::as_path)
+    }
+
+    /// Activates the tab of `path`, opened after the active one if needed.
+    pub fn open(&mut self, path: &Path) {
+        self.active = match self.tabs.iter().position(|p| p == path) {
+            Some(idx) => idx,
+            None => {
+                let idx = if self.tabs.is_empty() {
+                    0
+                } else {
+                    self.active + 1
+                };
+                self.tabs.insert(idx, path.to_path_buf());
+                idx
+            }
+        };
+    }
+
+    /// Closes tab `idx`; the next one becomes active, or else the previous.
+    pub fn close(&mut self, idx: usize) {
+        if idx >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(idx);
+        if idx < self.active || self.active == self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+    }
+
+    /// Moves tab `from` to position `to`; the active tab stays active.
+    pub fn move_tab(&mut self, from: usize, to: usize) {
+        if from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
+        let active = self.tabs[self.active].clone();
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        self.active = self.tabs.iter().position(|p| *p == active).unwrap_or(0);
+    }
+
+    /// Closes the tabs whose note is gone.
+    fn retain(&mut self, exists: &impl Fn(&Path) -> bool) {
+        let active = self.current().map(Path::to_path_buf);
+        self.tabs.retain(|p| exists(p));
+        self.active = active
+            .and_then(|a| self.tabs.iter().position(|p| *p == a))
+            .unwrap_or(0);
+    }
+}
+
+/// The panes of the window, restored on the next start.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    /// One or two.
+    pub panes: Vec<Pane>,
+    /// The pane edited, whose note is the selected one.
+    pub focused: usize,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self {
+            panes: vec![Pane::default()],
+            focused: 0,
+        }
+    }
+}
+
+impl Workspace {
+    pub fn focused(&self) -> &Pane {
+        &self.panes[self.focused]
+    }
+
+    pub fn focused_mut(&mut self) -> &mut Pane {
+        &mut self.panes[self.focused]
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.panes.len() > 1
+    }
+
+    /// Opens a second pane on the current note, or merges the second pane
+    /// back into the first.
+    pub fn toggle_split(&mut self) {
+        if let Some(second) = self.panes.get(1).cloned() {
+            self.panes.truncate(1);
+            for tab in &second.tabs {
+                if !self.panes[0].tabs.contains(tab) {
+                    self.panes[0].tabs.push(tab.clone());
+                }
+            }
+            if let Some(current) = second.current().filter(|_| self.focused == 1) {
+                self.panes[0].open(current);
+            }
+            self.focused = 0;
+        } else {
+            let mut pane = Pane::default();
+            if let Some(current) = self.panes[0].current() {
+                pane.open(current);
+            }
+            self.panes.push(pane);
+            self.focused = 1;
+        }
+    }
+
+    /// Closes tab `idx` of pane `pane`; a second pane left empty goes away.
+    pub fn close(&mut self, pane: usize, idx: usize) {
+        let Some(p) = self.panes.get_mut(pane) else {
+            return;
+        };
+        p.close(idx);
+        if p.tabs.is_empty() && pane > 0 {
+            self.panes.remove(pane);
+            self.focused = 0;
+        }
+    }
+
+    /// Closes the tabs whose note is gone, for instance after a restart.
+    pub fn retain(&mut self, exists: impl Fn(&Path) -> bool) {
+        for pane in &mut self.panes {
+            pane.retain(&exists);
+        }
+        if self.panes.is_empty() {
+            self.panes.push(Pane::default());
+        }
+        self.focused = self.focused.min(self.panes.len() - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(pane: &Pane) -> Vec<&str> {
+        pane.tabs.iter().map(|p| p.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn opens_closes_and_moves_tabs() {
+        let mut pane = Pane::default();
+        for name in ["a", "b", "c"] {
+            pane.open(Path::new(name));
+        }
+        pane.active = 0;
+        pane.open(Path::new("d"));
+        assert_eq!((paths(&pane), pane.active), (vec!["a", "d", "b", "c"], 1));
+        pane.open(Path::new("c"));
+        assert_eq!(pane.active, 3);
+
+        pane.move_tab(3, 0);
+        assert_eq!(
+            (paths(&pane), pane.current()),
+            (vec!["c", "a", "d", "b"], Some(Path::new("c")))
+        );
+        pane.close(1);
+        assert_eq!(pane.current(), Some(Path::new("c")));
+        pane.close(0);
+        assert_eq!(pane.current(), Some(Path::new("d")));
+        pane.close(1);
+        assert_eq!(
+            (paths(&pane), pane.current()),
+            (vec!["d"], Some(Path::new("d")))
+        );
+        pane.close(0);
+        assert_eq!(pane.current(), None);
+    }
+
+    #[test]
+    fn splits_and_merges_panes() {
+        let mut workspace = Workspace::default();
+        workspace.focused_mut().open(Path::new("a"));
+        workspace.toggle_split();
+        assert_eq!(
+            (workspace.focused, workspace.focused().current()),
+            (1, Some(Path::new("a")))
+        );
+        workspace.focused_mut().open(Path::new("b"));
+        workspace.toggle_split();
+        assert!(!workspace.is_split());
+        assert_eq!(paths(workspace.focused()), ["a", "b"]);
+        assert_eq!(workspace.focused().current(), Some(Path::new("b")));
+
+        workspace.toggle_split();
+        workspace.close(1, 0);
+        assert!(!workspace.is_split());
+        workspace.retain(|p| p != Path::new("b"));
+        assert_eq!(paths(workspace.focused()), ["a"]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Note templates: Markdown files in `.notes/templates`, whose variables are
+//! filled in when a note is created from them.
+
+use crate::vault::{self, Vault};
+use chrono::{Local, NaiveDate, NaiveTime};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+/// Where the cursor goes in a note created from a template.
+pub const CURSOR: &str = "{{cursor}}";
+
+/// Values of `{{date}}`, `{{time}}`, `{{title}}` and `{{folder}}`.
+pub struct Vars<'a> {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub title: &'a str,
+    pub folder: &'a str,
+}
+
+impl<'a> Vars<'a> {
+    pub fn now(title: &'a str, folder: &'a str) -> Self {
+        let now = Local::now().naive_local();
+        Self {
+            date: now.date(),
+            time: now.time(),
+            title,
+            folder,
+        }
+    }
+}
+
+pub fn dir(vault: &Vault) -> PathBuf {
+    vault.config_dir().join("templates")
+}
+
+/// Names of the templates (file names without `.md`), sorted.
+pub fn names(vault: &Vault) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(dir(vault)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "md") {
+            if let Some(stem) = path.file_stem() {
+                names.push(stem.to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Text of the template `name`, or `None` when there is no such template.
+pub fn load(vault: &Vault, name: &str) -> io::Result<Option<String>> {
+    if vault::file_name(name) != name {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid template: {name}"),
+        ));
+    }
+    match fs::read_to_string(dir(vault).join(format!("{name}.md"))) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fills in the variables of `template`. Unknown ones are left as they are.
+/// Returns the text and the position of the cursor marker, in characters,
+/// which is removed.
+pub fn expand(template: &str, vars: &Vars) -> (String, Option<usize>) {
+    // le marqueur d'abord, pour qu'un titre contenant {{cursor}} reste tel quel
+    let (before, after) = match template.split_once(CURSOR) {
+        Some((before, after)) => (before, Some(after)),
+        None => (template, None),
+    };
+    let before = fill(before, vars);
+    match after {
+        Some(after) => {
+            let cursor = before.chars().count();
+            (
+                before + &fill(&after.replace(CURSOR, ""), vars),
+                Some(cursor),
+            )
+        }
+        None => (before, None),
+    }
+}
+
+fn fill(text: &str, vars: &Vars) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let value = match &rest[start + 2..start + len] {
+            "date" => vars.date.format("%Y-%m-%d").to_string(),
+            "time" => vars.time.format("%H:%M").to_string(),
+            "title" => vars.title.to_owned(),
+            "folder" => vars.folder.to_owned(),
+            _ => rest[start..start + len + 2].to_owned(),
+        };
+        out.push_str(&value);
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_variables_and_places_the_cursor() {
+        let vars = Vars {
+            date: NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(),
+            time: NaiveTime::from_hms_opt(14, 30, 0).unwrap(),
+            title: "Réunion {{date}}",
+            folder: "Travail",
+        };
+        let (text, cursor) = expand(
+            "# {{title}}\n{{date}} {{time}}, {{folder}} {{x}}\n\n- {{cursor}}\n{{date",
+            &vars,
+        );
+        assert_eq!(
+            text,
+            "# Réunion {{date}}\n2026-10-19 14:30, Travail {{x}}\n\n- \n{{date"
+        );
+        assert_eq!(
+            cursor,
+            Some(
+                "# Réunion {{date}}\n2026-10-19 14:30, Travail {{x}}\n\n- "
+                    .chars()
+                    .count()
+            )
+        );
+        assert_eq!(expand("{{folder}}", &vars), ("Travail".to_owned(), None));
+    }
+
+    #[test]
+    fn lists_and_loads_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new(dir.path());
+        assert!(names(&vault).unwrap().is_empty());
+        fs::create_dir_all(super::dir(&vault)).unwrap();
+        fs::write(super::dir(&vault).join("Réunion.md"), "# {{title}}").unwrap();
+        fs::write(super::dir(&vault).join("Incident.md"), "").unwrap();
+        fs::write(super::dir(&vault).join("notes.txt"), "").unwrap();
+
+        assert_eq!(names(&vault).unwrap(), ["Incident", "Réunion"]);
+        assert_eq!(
+            load(&vault, "Réunion").unwrap().as_deref(),
+            Some("# {{title}}")
+        );
+        assert_eq!(load(&vault, "Absent").unwrap(), None);
+        assert!(load(&vault, "../settings").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::app::Msg;
+use chrono::{Datelike, Months, NaiveDate};
+use egui::{self, Color32, RichText, Ui};
+
+const MONTHS: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const WEEKDAYS: [&str; 7] = ["lu", "ma", "me", "je", "ve", "sa", "di"];
+
+/// Month view of the journal: days that have a note stand out, a click
+/// opens (or creates) the note of the day.
+pub struct Calendar<'a> {
+    month: NaiveDate,
+    days: &'a [NaiveDate],
+    today: NaiveDate,
+    selected: Option<NaiveDate>,
+}
+
+impl<'a> Calendar<'a> {
+    /// `month` is any day of the month shown; `days` is sorted.
+    pub fn new(
+        month: NaiveDate,
+        days: &'a [NaiveDate],
+        today: NaiveDate,
+        selected: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            month: month.with_day(1).unwrap_or(month),
+            days,
+            today,
+            selected,
+        }
+    }
+
+    pub fn render(&self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        ui.horizontal(|ui| {
+            if ui.button("⏴").on_hover_text("Mois précédent").clicked() {
+                msg = self
+                    .month
+                    .checked_sub_months(Months::new(1))
+                    .map(Msg::ShowMonth);
+            }
+            let title = format!(
+                "{} {}",
+                MONTHS[self.month.month0() as usize],
+                self.month.year()
+            );
+            ui.label(RichText::new(title).strong());
+            if ui.button("⏵").on_hover_text("Mois suivant").clicked() {
+                msg = self
+                    .month
+                    .checked_add_months(Months::new(1))
+                    .map(Msg::ShowMonth);
+            }
+            if ui.button("Aujourd’hui").clicked() {
+                msg = Some(Msg::OpenDay(self.today));
+            }
+        });
+
+        let accent = ui.visuals().selection.stroke.color;
+        egui::Grid::new("calendar")
+            .spacing([4.0, 2.0])
+            .show(ui, |ui| {
+                for name in WEEKDAYS {
+                    ui.label(RichText::new(name).small().color(Color32::GRAY));
+                }
+                ui.end_row();
+                // lundi en première colonne
+                for _ in 0..self.month.weekday().num_days_from_monday() {
+                    ui.label("");
+                }
+                for day in self
+                    .month
+                    .iter_days()
+                    .take_while(|d| d.month() == self.month.month())
+                {
+                    let mut text = RichText::new(format!("{:>2}", day.day())).monospace();
+                    if self.days.binary_search(&day).is_ok() {
+                        text = text.strong().color(accent);
+                    }
+                    if day == self.today {
+                        text = text.underline();
+                    }
+                    if ui
+                        .selectable_label(self.selected == Some(day), text)
+                        .clicked()
+                    {
+                        msg = Some(Msg::OpenDay(day));
+                    }
+                    if day.weekday().num_days_from_monday() == 6 {
+                        ui.end_row();
+                    }
+                }
+            });
+        msg
+    }
+}
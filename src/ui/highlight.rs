@@ -0,0 +1,161 @@
+//! Styles of the editor's text in the inline preview mode.
+
+use egui::text::{LayoutJob, TextFormat};
+use egui::TextStyle;
+use notes_app::render;
+use pulldown_cmark::{Event, Tag};
+
+/// Layout of the text of the editor in the inline preview: every character
+/// of `text` stays, so that the cursor matches the galley, and the Markdown
+/// only changes the styles; its syntax characters are dimmed.
+pub fn markdown_job(text: &str, style: &egui::Style) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let mut fmt = TextFormat {
+        font_id: TextStyle::Body.resolve(style),
+        color: style.visuals.text_color(),
+        ..Default::default()
+    };
+    let mut stack: Vec<TextFormat> = Vec::new();
+    // ce qui précède `pos` est déjà dans le job
+    let mut pos = 0;
+    let syntax = |job: &mut LayoutJob, pos: &mut usize, end: usize, fmt: &TextFormat| {
+        if end > *pos {
+            let dimmed = TextFormat {
+                color: style.visuals.weak_text_color(),
+                ..fmt.clone()
+            };
+            job.append(&text[*pos..end], 0.0, dimmed);
+            *pos = end;
+        }
+    };
+
+    for (ev, range) in render::parser(text).into_offset_iter() {
+        match ev {
+            Event::Start(tag) => {
+                syntax(&mut job, &mut pos, range.start, &fmt);
+                stack.push(fmt.clone());
+                match tag {
+                    Tag::Heading(level, _, _) => {
+                        let mut id = TextStyle::Heading.resolve(style);
+                        let lvl: usize = level as usize;
+                        id.size -= (lvl.saturating_sub(1) as f32) * 2.0;
+                        fmt.font_id = id;
+                    }
+                    Tag::Emphasis => fmt.italics = true,
+                    Tag::Strong => fmt.color = style.visuals.strong_text_color(),
+                    Tag::CodeBlock(_) => fmt.font_id = TextStyle::Monospace.resolve(style),
+                    _ => {}
+                }
+            }
+            Event::End(_) => {
+                // les marqueurs de fin (`**`, `_`) gardent le style du bloc
+                syntax(&mut job, &mut pos, range.end, &fmt);
+                fmt = stack.pop().unwrap_or(fmt);
+            }
+            Event::Text(_) if range.end > pos => {
+                syntax(&mut job, &mut pos, range.start, &fmt);
+                job.append(&text[pos..range.end], 0.0, fmt.clone());
+                pos = range.end;
+            }
+            Event::Code(_) if range.end > pos => {
+                syntax(&mut job, &mut pos, range.start, &fmt);
+                let code_fmt = TextFormat {
+                    font_id: TextStyle::Monospace.resolve(style),
+                    ..fmt.clone()
+                };
+                // les accents graves autour du code sont de la syntaxe
+                let ticks = text[pos..range.end]
+                    .bytes()
+                    .take_while(|b| *b == b'`')
+                    .count();
+                let start = pos + ticks;
+                syntax(&mut job, &mut pos, start, &code_fmt);
+                job.append(&text[pos..range.end - ticks], 0.0, code_fmt.clone());
+                pos = range.end - ticks;
+                syntax(&mut job, &mut pos, range.end, &code_fmt);
+            }
+            _ => {}
+        }
+    }
+    syntax(&mut job, &mut pos, text.len(), &fmt);
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_layout_has_no_extra_newline() {
+        let style = egui::Style::default();
+        let text = "# les";
+        let job = markdown_job(text, &style);
+        assert!(!job.text.contains('\n'));
+    }
+
+    #[test]
+    fn heading_layout_preserves_text_order() {
+        let style = egui::Style::default();
+        let text = "# les";
+        let job = markdown_job(text, &style);
+        assert_eq!(job.text, "# les");
+    }
+
+    #[test]
+    fn inline_layout_keeps_every_character() {
+        let style = egui::Style::default();
+        let text = "# Titre\n\n- **gras** et _penché_\n  - `code` &amp; \\*\n\n```rust\nfn main() {}\n```\n> cité\n";
+        let job = markdown_job(text, &style);
+        assert_eq!(job.text, text);
+    }
+
+    #[test]
+    fn inline_layout_dims_the_syntax() {
+        let style = egui::Style::default();
+        let text = "# Titre\n\n**gras** et _penché_, `code`\n\n```rust\nfn main() {}\n```\n";
+        let job = markdown_job(text, &style);
+        let format_of = |needle: &str| {
+            let at = text.find(needle).unwrap();
+            job.sections
+                .iter()
+                .find(|s| s.byte_range.contains(&at))
+                .unwrap()
+                .format
+                .clone()
+        };
+        let (weak, body) = (style.visuals.weak_text_color(), style.visuals.text_color());
+        let heading = format_of("Titre").font_id;
+        let mono = TextStyle::Monospace.resolve(&style);
+        assert!(heading.size > TextStyle::Body.resolve(&style).size);
+
+        // le `#` a la taille du titre, estompé
+        assert_eq!(
+            (format_of("#").color, format_of("#").font_id),
+            (weak, heading)
+        );
+        assert_eq!(format_of("Titre").color, body);
+        assert_eq!(format_of("**").color, weak);
+        assert_eq!(format_of("gras").color, style.visuals.strong_text_color());
+        let underscore = format_of("_");
+        assert!(underscore.italics);
+        assert_eq!(underscore.color, weak);
+        assert!(format_of("penché").italics);
+        assert_eq!(
+            (format_of("`").color, format_of("`").font_id),
+            (weak, mono.clone())
+        );
+        assert_eq!(
+            (format_of("code").color, format_of("code").font_id),
+            (body, mono.clone())
+        );
+        assert_eq!(
+            (format_of("```").color, format_of("```").font_id),
+            (weak, mono.clone())
+        );
+        assert_eq!(
+            (format_of("fn main").color, format_of("fn main").font_id),
+            (body, mono)
+        );
+        assert_eq!(format_of(" et ").color, body);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::app::Msg;
+use egui::{self, Color32, RichText, Ui};
+use notes_app::git::Commit;
+use notes_app::history::{self, Change, Version};
+use std::path::PathBuf;
+
+/// History panel of the note at `path`.
+pub struct HistoryView {
+    pub path: PathBuf,
+    /// Shows the commits of the note rather than its local versions.
+    pub git: bool,
+    pub versions: Vec<Version>,
+    pub commits: Vec<Commit>,
+    pub selected: Option<usize>,
+    /// Text of the selected version.
+    pub text: String,
+}
+
+impl HistoryView {
+    pub fn labels(&self) -> Vec<String> {
+        fn time(t: i64) -> String {
+            history::local_time(t)
+                .map(|t| t.format("%d/%m/%Y %H:%M:%S").to_string())
+                .unwrap_or_default()
+        }
+        if self.git {
+            self.commits
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{} {} ({})",
+                        time(c.time),
+                        c.summary,
+                        &c.hash[..7.min(c.hash.len())]
+                    )
+                })
+                .collect()
+        } else {
+            self.versions.iter().map(|v| time(v.time)).collect()
+        }
+    }
+}
+
+/// Versions of a note on the left, diff of the chosen one against the
+/// current text on the right.
+pub struct HistoryPanel<'a> {
+    /// One label per version, newest first.
+    versions: &'a [String],
+    selected: Option<usize>,
+    /// Text of the selected version.
+    text: &'a str,
+    current: &'a str,
+    /// Whether the versions are commits, `None` outside a git repository.
+    git: Option<bool>,
+}
+
+impl<'a> HistoryPanel<'a> {
+    pub fn new(
+        versions: &'a [String],
+        selected: Option<usize>,
+        text: &'a str,
+        current: &'a str,
+        git: Option<bool>,
+    ) -> Self {
+        Self {
+            versions,
+            selected,
+            text,
+            current,
+            git,
+        }
+    }
+
+    pub fn render(&self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        ui.horizontal(|ui| {
+            ui.heading("Historique");
+            if ui.button("Fermer").clicked() {
+                msg = Some(Msg::CloseHistory);
+            }
+            if self.selected.is_some() && ui.button("Restaurer cette version").clicked() {
+                msg = Some(Msg::RestoreVersion);
+            }
+            if let Some(git) = self.git {
+                ui.separator();
+                if ui.selectable_label(!git, "Versions locales").clicked() && git {
+                    msg = Some(Msg::ShowGitHistory(false));
+                }
+                if ui.selectable_label(git, "Git").clicked() && !git {
+                    msg = Some(Msg::ShowGitHistory(true));
+                }
+            }
+        });
+        ui.separator();
+
+        if self.versions.is_empty() {
+            ui.colored_label(Color32::GRAY, "Aucune version enregistrée pour cette note.");
+            return msg;
+        }
+        ui.columns(2, |cols| {
+            egui::ScrollArea::vertical()
+                .id_salt("versions")
+                .show(&mut cols[0], |ui| {
+                    for (idx, label) in self.versions.iter().enumerate() {
+                        if ui
+                            .selectable_label(self.selected == Some(idx), label)
+                            .clicked()
+                        {
+                            msg = Some(Msg::SelectVersion(idx));
+                        }
+                    }
+                });
+            egui::ScrollArea::vertical()
+                .id_salt("diff")
+                .show(&mut cols[1], |ui| {
+                    if self.selected.is_none() {
+                        ui.colored_label(Color32::GRAY, "Choisissez une version.");
+                        return;
+                    }
+                    // ce que la restauration retirerait (−) ou ramènerait (+)
+                    let changes = history::diff(self.current, self.text);
+                    if changes.iter().all(|c| matches!(c, Change::Same(_))) {
+                        ui.colored_label(Color32::GRAY, "Identique au texte actuel.");
+                    }
+                    for change in changes {
+                        let (sign, line, color) = match change {
+                            Change::Same(line) => (' ', line, ui.visuals().weak_text_color()),
+                            Change::Added(line) => ('+', line, Color32::from_rgb(60, 170, 80)),
+                            Change::Removed(line) => ('−', line, Color32::from_rgb(210, 70, 60)),
+                        };
+                        let line = line.strip_suffix('\n').unwrap_or(line);
+                        ui.label(
+                            RichText::new(format!("{sign} {line}"))
+                                .monospace()
+                                .color(color),
+                        );
+                    }
+                });
+        });
+        msg
+    }
+}
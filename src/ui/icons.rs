@@ -0,0 +1,39 @@
+use egui::{self, TextureHandle};
+
+#[derive(Clone)]
+pub struct Icons {
+    pub search: TextureHandle,
+    pub back: TextureHandle,
+    pub home: TextureHandle,
+    pub settings: TextureHandle,
+    pub add: TextureHandle,
+}
+
+impl Icons {
+    pub fn load(ctx: &egui::Context) -> Self {
+        fn png(bytes: &[u8], ctx: &egui::Context, id: &str) -> TextureHandle {
+            let img = image::load_from_memory(bytes).unwrap().to_rgba8();
+            let sz = [img.width() as usize, img.height() as usize];
+            ctx.load_texture(
+                id,
+                egui::ColorImage::from_rgba_unmultiplied(sz, &img),
+                egui::TextureOptions::LINEAR,
+            )
+        }
+        Self {
+            search: png(
+                include_bytes!("../../assets/search.png"),
+                ctx,
+                "icon_search",
+            ),
+            back: png(include_bytes!("../../assets/back.png"), ctx, "icon_back"),
+            home: png(include_bytes!("../../assets/home.png"), ctx, "icon_home"),
+            settings: png(
+                include_bytes!("../../assets/setting.png"),
+                ctx,
+                "icon_settings",
+            ),
+            add: png(include_bytes!("../../assets/add.png"), ctx, "icon_add"),
+        }
+    }
+}
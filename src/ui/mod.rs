@@ -1,3 +1,11 @@
-pub mod sidebar;
+pub mod calendar;
+pub mod highlight;
+pub mod history;
+pub mod icons;
 pub mod notes_panel;
+pub mod palette;
+pub mod preview;
 pub mod settings;
+pub mod sidebar;
+pub mod tabs;
+pub mod unlock;
@@ -1,43 +1,104 @@
+use crate::app::Msg;
 use egui::{self, Ui};
-use crate::models::Folder;
+use notes_app::crypt;
+use notes_app::models::Folder;
 
 pub struct NotesPanel<'a> {
     folders: &'a [Folder],
     current: Option<usize>,
+    templates: &'a [String],
+    /// Whether the current folder is encrypted.
+    encrypted: bool,
 }
 
 impl<'a> NotesPanel<'a> {
-    pub fn new(folders: &'a [Folder], current: Option<usize>) -> Self {
-        Self { folders, current }
+    pub fn new(
+        folders: &'a [Folder],
+        current: Option<usize>,
+        templates: &'a [String],
+        encrypted: bool,
+    ) -> Self {
+        Self {
+            folders,
+            current,
+            templates,
+            encrypted,
+        }
     }
 
-    pub fn render(&self, ui: &mut Ui) {
+    pub fn render(&self, ui: &mut Ui) -> Option<Msg> {
         let heading = match self.current {
             Some(idx) => &self.folders[idx].name,
             None => "Notes",
         };
-        ui.heading(heading);
-        ui.add_space(12.0);
+        let mut msg = None;
+        ui.horizontal(|ui| {
+            ui.heading(heading);
+            if self.current.is_none() {
+                return;
+            }
+            ui.menu_button("Nouvelle note", |ui| {
+                if ui.button("Note vide").clicked() {
+                    msg = Some(Msg::CreateNote(None));
+                    ui.close_menu();
+                }
+                if !self.templates.is_empty() {
+                    ui.separator();
+                }
+                for name in self.templates {
+                    if ui.button(name).clicked() {
+                        msg = Some(Msg::CreateNote(Some(name.clone())));
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui.button("Exporter en HTML…").clicked() {
+                msg = Some(Msg::ExportFolder);
+            }
+            let label = if self.encrypted {
+                "🔓 Ne plus chiffrer le dossier"
+            } else {
+                "🔒 Chiffrer le dossier"
+            };
+            if ui.button(label).clicked() {
+                msg = Some(Msg::EncryptFolder(!self.encrypted));
+            }
+        });
+        ui.add_space(8.0);
 
         if let Some(idx) = self.current {
-            list_notes(ui, &self.folders[idx]);
+            if let Some(m) = list_notes(ui, &self.folders[idx]) {
+                msg = Some(m);
+            }
         } else {
             ui.colored_label(
                 egui::Color32::GRAY,
                 "Sélectionnez un dossier dans la barre latérale.",
             );
         }
+        msg
     }
 }
 
-fn list_notes(ui: &mut Ui, folder: &Folder) {
+fn list_notes(ui: &mut Ui, folder: &Folder) -> Option<Msg> {
+    let mut msg = None;
     egui::ScrollArea::vertical().show(ui, |ui| {
-        for note in &folder.notes {
-            ui.group(|ui| {
-                ui.label(&note.title);
-                ui.label(&note.body);
-            });
-            ui.add_space(8.0);
+        for (idx, note) in folder.notes.iter().enumerate() {
+            // pas d'aperçu pour une note encore chiffrée
+            let preview = if crypt::is_encrypted(&note.body) {
+                crypt::PLACEHOLDER
+            } else {
+                &note.body
+            };
+            if ui
+                .selectable_label(false, &note.title)
+                .on_hover_text(preview)
+                .clicked()
+            {
+                msg = Some(Msg::SelectNote(idx));
+            }
+            ui.add_space(4.0);
         }
     });
+    msg
 }
@@ -0,0 +1,101 @@
+use crate::app::Msg;
+use egui::{self, Align2, Color32, Context, Key, Modifiers, TextEdit};
+
+/// A line of the palette.
+#[derive(Clone)]
+pub struct PaletteEntry {
+    pub label: String,
+    /// Shortcut of an action, folder or snippet of a note.
+    pub detail: String,
+    pub msg: Msg,
+}
+
+/// Query field over a list of commands and notes, driven by the keyboard:
+/// ↑ and ↓ choose, Entrée runs, Échap closes.
+pub struct CommandPalette<'a> {
+    query: &'a mut String,
+    selected: &'a mut usize,
+    entries: &'a [PaletteEntry],
+    hint: &'a str,
+}
+
+impl<'a> CommandPalette<'a> {
+    pub fn new(
+        query: &'a mut String,
+        selected: &'a mut usize,
+        entries: &'a [PaletteEntry],
+        hint: &'a str,
+    ) -> Self {
+        Self {
+            query,
+            selected,
+            entries,
+            hint,
+        }
+    }
+
+    pub fn render(self, ctx: &Context) -> Option<Msg> {
+        let mut msg = None;
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        let last = self.entries.len().saturating_sub(1);
+        if up {
+            *self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            *self.selected = (*self.selected + 1).min(last);
+        }
+        *self.selected = (*self.selected).min(last);
+        if escape {
+            return Some(Msg::ClosePalette);
+        }
+        if enter {
+            return self.entries.get(*self.selected).map(|e| e.msg.clone());
+        }
+
+        egui::Window::new("palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, [0.0, 40.0])
+            .fixed_size([480.0, 0.0])
+            .show(ctx, |ui| {
+                let field = TextEdit::singleline(self.query).hint_text(self.hint);
+                let field = ui.add(field.desired_width(f32::INFINITY));
+                field.request_focus();
+                ui.separator();
+                if self.entries.is_empty() {
+                    ui.colored_label(Color32::GRAY, "Aucun résultat.");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        for (idx, entry) in self.entries.iter().enumerate() {
+                            let chosen = idx == *self.selected;
+                            let response = ui.horizontal(|ui| {
+                                let label = ui.selectable_label(chosen, &entry.label);
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        ui.colored_label(Color32::GRAY, &entry.detail);
+                                    },
+                                );
+                                label
+                            });
+                            if chosen && (up || down) {
+                                response.inner.scroll_to_me(None);
+                            }
+                            if response.inner.clicked() {
+                                msg = Some(entry.msg.clone());
+                            }
+                        }
+                    });
+            });
+        msg
+    }
+}
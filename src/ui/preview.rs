@@ -0,0 +1,311 @@
+use crate::app::Msg;
+use egui::{self, Galley, ScrollArea, Ui};
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use notes_app::render;
+use pulldown_cmark::{Event, Tag};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// How a note shows: its text, its rendering, or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewMode {
+    /// The text only.
+    Editor,
+    /// The rendering only.
+    Preview,
+    /// The text, and the rendering in a side panel that follows it.
+    #[default]
+    Split,
+    /// The text, formatted as it is typed.
+    Inline,
+}
+
+impl PreviewMode {
+    pub const ALL: [PreviewMode; 4] = [
+        PreviewMode::Editor,
+        PreviewMode::Preview,
+        PreviewMode::Split,
+        PreviewMode::Inline,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PreviewMode::Editor => "Éditeur",
+            PreviewMode::Preview => "Aperçu",
+            PreviewMode::Split => "Côte à côte",
+            PreviewMode::Inline => "Aperçu en ligne",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// A position in the note and the height where it shows in a view.
+type Anchor = (usize, f32);
+
+/// Keeps the editor and the preview of a note at the same place: the view
+/// the user scrolls leads, the other one follows on the next frame.
+#[derive(Default)]
+pub struct ScrollSync {
+    /// Start of each row of the editor.
+    editor: Vec<Anchor>,
+    /// Start and end of each block of the preview.
+    preview: Vec<Anchor>,
+    editor_offset: f32,
+    preview_offset: f32,
+    editor_goal: Option<f32>,
+    preview_goal: Option<f32>,
+}
+
+impl ScrollSync {
+    /// Offset the editor should scroll to, if the preview moved.
+    pub fn editor_goal(&mut self) -> Option<f32> {
+        self.editor_goal.take()
+    }
+
+    /// The editor showed at `offset`; `followed` when it was given its
+    /// offset by [`Self::editor_goal`].
+    pub fn editor_shown(&mut self, offset: f32, anchors: Vec<Anchor>, followed: bool) {
+        if !followed && (offset - self.editor_offset).abs() > 0.5 {
+            self.preview_goal = Some(y_at(&self.preview, byte_at(&anchors, offset)));
+        }
+        self.editor = anchors;
+        self.editor_offset = offset;
+    }
+
+    fn preview_shown(&mut self, offset: f32, anchors: Vec<Anchor>, followed: bool) {
+        if !followed && (offset - self.preview_offset).abs() > 0.5 {
+            self.editor_goal = Some(y_at(&self.editor, byte_at(&anchors, offset)));
+        }
+        self.preview = anchors;
+        self.preview_offset = offset;
+    }
+}
+
+/// Height of byte `byte`, between the anchors around it.
+fn y_at(anchors: &[Anchor], byte: usize) -> f32 {
+    match anchors.iter().position(|&(b, _)| b > byte) {
+        None => anchors.last().map_or(0.0, |a| a.1),
+        Some(0) => anchors[0].1,
+        Some(i) => {
+            let ((b0, y0), (b1, y1)) = (anchors[i - 1], anchors[i]);
+            y0 + (y1 - y0) * (byte - b0) as f32 / (b1 - b0) as f32
+        }
+    }
+}
+
+/// Byte at height `y`, between the anchors around it.
+fn byte_at(anchors: &[Anchor], y: f32) -> usize {
+    match anchors.iter().position(|&(_, h)| h > y) {
+        None => anchors.last().map_or(0, |a| a.0),
+        Some(0) => anchors[0].0,
+        Some(i) => {
+            let ((b0, y0), (b1, y1)) = (anchors[i - 1], anchors[i]);
+            b0 + ((b1 - b0) as f32 * (y - y0) / (y1 - y0)) as usize
+        }
+    }
+}
+
+/// Start of each row of `galley`, the layout of `text` placed at `top`.
+pub fn row_anchors(text: &str, galley: &Galley, top: f32) -> Vec<Anchor> {
+    let mut byte = 0;
+    let mut anchors = Vec::with_capacity(galley.rows.len());
+    for row in &galley.rows {
+        anchors.push((byte, top + row.min_y()));
+        let chars = row.char_count_including_newline();
+        byte = text[byte..]
+            .char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(i, _)| byte + i);
+    }
+    anchors
+}
+
+/// Parts of `md` rendered one after the other: its blocks, except that the
+/// blocks from a footnote reference to its definition stay together. Each
+/// part is followed by the link reference definitions of the whole note,
+/// which render as nothing, so that its links resolve as in the whole note.
+fn parts(md: &str) -> Vec<(Range<usize>, String)> {
+    let blocks = render::blocks(md);
+    let block_of = |offset: usize| blocks.iter().position(|b| b.end > offset);
+    let parser = render::parser(md);
+    let mut definitions: Vec<Range<usize>> = parser
+        .reference_definitions()
+        .iter()
+        .map(|(_, def)| def.span.clone())
+        .collect();
+    definitions.sort_by_key(|span| span.start);
+    let definitions: String = definitions
+        .into_iter()
+        .map(|span| format!("\n{}", md[span].trim_end()))
+        .collect();
+
+    // une note de bas de page et ses renvois dans la même partie
+    let (mut refs, mut notes) = (Vec::new(), Vec::new());
+    for (ev, range) in parser.into_offset_iter() {
+        match ev {
+            Event::FootnoteReference(label) => refs.push((label, range.start)),
+            Event::Start(Tag::FootnoteDefinition(label)) => notes.push((label, range.start)),
+            _ => {}
+        }
+    }
+    // dernier bloc de la partie commençant à chaque bloc
+    let mut joined: Vec<usize> = (0..blocks.len()).collect();
+    for (label, at) in &refs {
+        let note = notes.iter().find(|(l, _)| l == label);
+        if let Some((Some(a), Some(b))) = note.map(|(_, def)| (block_of(*at), block_of(*def))) {
+            let (first, last) = (a.min(b), a.max(b));
+            joined[first] = joined[first].max(last);
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < blocks.len() {
+        let mut last = joined[i];
+        let mut j = i;
+        while j < last {
+            j += 1;
+            last = last.max(joined[j]);
+        }
+        let range = blocks[i].start..blocks[last].end;
+        let text = format!("{}\n{definitions}", &md[range.clone()]);
+        parts.push((range, text));
+        i = last + 1;
+    }
+    parts
+}
+
+/// Rendered note, one part after the other so that each part can be matched
+/// with its lines in the editor (see [`parts`]). Wiki-links open their note.
+pub struct Preview<'a> {
+    body: &'a str,
+    cache: &'a mut CommonMarkCache,
+    sync: Option<&'a mut ScrollSync>,
+    id: egui::Id,
+}
+
+impl<'a> Preview<'a> {
+    pub fn new(
+        body: &'a str,
+        cache: &'a mut CommonMarkCache,
+        sync: Option<&'a mut ScrollSync>,
+        id: egui::Id,
+    ) -> Self {
+        Self {
+            body,
+            cache,
+            sync,
+            id,
+        }
+    }
+
+    pub fn render(mut self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        let goal = self.sync.as_mut().and_then(|s| s.preview_goal.take());
+        let mut area = ScrollArea::vertical().id_salt(self.id).auto_shrink(false);
+        if let Some(offset) = goal {
+            area = area.vertical_scroll_offset(offset);
+        }
+        let output = area.show(ui, |ui| {
+            let top = ui.cursor().top();
+            let mut anchors = Vec::new();
+            for (i, (range, part)) in parts(self.body).into_iter().enumerate() {
+                anchors.push((range.start, ui.cursor().top() - top));
+                // les liens [[…]] deviennent des liens cliquables
+                let md = render::link_wiki_links(&part);
+                self.cache.link_hooks_clear();
+                for dest in note_links(&md) {
+                    self.cache.add_link_hook(dest);
+                }
+                ui.push_id((self.id, i), |ui| {
+                    CommonMarkViewer::new().show(ui, self.cache, &md)
+                });
+                let clicked = self.cache.link_hooks().iter().find(|(_, &clicked)| clicked);
+                if let Some((dest, _)) = clicked {
+                    msg = Some(Msg::OpenLink(dest[render::NOTE_SCHEME.len()..].to_owned()));
+                }
+                anchors.push((range.end, ui.cursor().top() - top));
+            }
+            anchors
+        });
+        if let Some(sync) = self.sync {
+            sync.preview_shown(output.state.offset.y, output.inner, goal.is_some());
+        }
+        msg
+    }
+}
+
+/// Destinations of the links of `md` to other notes.
+fn note_links(md: &str) -> Vec<String> {
+    render::parser(md)
+        .filter_map(|ev| match ev {
+            Event::Start(Tag::Link(_, dest, _)) if dest.starts_with(render::NOTE_SCHEME) => {
+                Some(dest.into_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_bytes_and_heights_between_anchors() {
+        // deux blocs : octets 0..10 sur 0..40, puis 12..20 sur 50..90
+        let anchors = [(0, 0.0), (10, 40.0), (12, 50.0), (20, 90.0)];
+        assert_eq!(y_at(&anchors, 5), 20.0);
+        assert_eq!(y_at(&anchors, 16), 70.0);
+        assert_eq!(y_at(&anchors, 99), 90.0);
+        assert_eq!(byte_at(&anchors, 20.0), 5);
+        assert_eq!(byte_at(&anchors, 45.0), 11);
+        assert_eq!(byte_at(&anchors, 500.0), 20);
+        assert_eq!((y_at(&[], 3), byte_at(&[], 3.0)), (0.0, 0));
+    }
+
+    #[test]
+    fn parts_resolve_references_and_footnotes() {
+        let md = "# Titre\n\nVoir [le site][site] et la note[^1].\n\nMilieu\n\nFin\n\n\
+                  [site]: https://example.com\n\n[^1]: La note.\n";
+        let split = parts(md);
+        let texts: Vec<&str> = split
+            .iter()
+            .map(|(r, _)| md[r.clone()].trim_end())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "# Titre",
+                "Voir [le site][site] et la note[^1].\n\nMilieu\n\nFin\n\n\
+                            [site]: https://example.com\n\n[^1]: La note."
+            ]
+        );
+
+        // chaque partie se suffit à elle-même
+        for (_, text) in &split {
+            let events: Vec<Event> = render::parser(text).collect();
+            for ev in &events {
+                if let Event::FootnoteReference(label) = ev {
+                    let defined = Event::Start(Tag::FootnoteDefinition(label.clone()));
+                    assert!(events.contains(&defined), "{text}");
+                }
+            }
+        }
+        // la définition d'un lien sert aux parties qui ne la contiennent pas
+        let md = "[a][site]\n\nb\n\n[site]: https://example.com\n";
+        let split = parts(md);
+        assert_eq!(split.len(), 2);
+        let dests: Vec<String> = render::parser(&split[0].1)
+            .filter_map(|ev| match ev {
+                Event::Start(Tag::Link(_, dest, _)) => Some(dest.into_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dests, ["https://example.com"]);
+    }
+}
@@ -1,34 +1,265 @@
-use egui::{self, Ui, Color32, Context};
-
-pub struct SettingsPanel<'a> {
-    dark_mode: &'a mut bool,
-}
-
-impl<'a> SettingsPanel<'a> {
-    pub fn new(dark_mode: &'a mut bool) -> Self {
-        Self { dark_mode }
-    }
-
-    pub fn render(&mut self, ctx: &Context, ui: &mut Ui) {
-        ui.heading("Paramètres");
-        ui.separator();
-
-        ui.horizontal(|ui| {
-            ui.label("Mode sombre");
-            if ui.checkbox(self.dark_mode, "").changed() {
-                ctx.set_visuals(if *self.dark_mode {
-                    egui::Visuals::dark()
-                } else {
-                    egui::Visuals::light()
-                });
-            }
-        });
-
-        ui.separator();
-        ui.label(
-            egui::RichText::new("Version 0.1 – Demo")
-                .color(Color32::GRAY)
-                .small(),
-        );
-    }
-}
+use crate::app::Msg;
+use egui::{self, Color32, Context, Ui};
+use notes_app::git::Status;
+use notes_app::models::Folder;
+use notes_app::settings::Settings;
+use std::path::Path;
+
+pub struct SettingsPanel<'a> {
+    dark_mode: &'a mut bool,
+    vault: Option<&'a Path>,
+    settings: &'a mut Settings,
+    folders: &'a [Folder],
+    templates: &'a [String],
+    /// `None` when the vault is not a git repository.
+    git: Option<&'a Result<Status, String>>,
+    git_busy: bool,
+}
+
+impl<'a> SettingsPanel<'a> {
+    pub fn new(
+        dark_mode: &'a mut bool,
+        vault: Option<&'a Path>,
+        settings: &'a mut Settings,
+        folders: &'a [Folder],
+        templates: &'a [String],
+        git: Option<&'a Result<Status, String>>,
+        git_busy: bool,
+    ) -> Self {
+        Self {
+            dark_mode,
+            vault,
+            settings,
+            folders,
+            templates,
+            git,
+            git_busy,
+        }
+    }
+
+    pub fn render(&mut self, ctx: &Context, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        ui.heading("Paramètres");
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Mode sombre");
+            if ui.checkbox(self.dark_mode, "").changed() {
+                ctx.set_visuals(if *self.dark_mode {
+                    egui::Visuals::dark()
+                } else {
+                    egui::Visuals::light()
+                });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Dossier de notes");
+            if let Some(vault) = self.vault {
+                ui.monospace(vault.display().to_string());
+            }
+            if ui.button("Changer…").clicked() {
+                msg = Some(Msg::ChooseVault);
+            }
+        });
+
+        ui.separator();
+        ui.label(egui::RichText::new("Capture rapide (notes_app --capture)").strong());
+        let names: Vec<&str> = self.folders.iter().map(|f| f.name.as_str()).collect();
+
+        ui.horizontal(|ui| {
+            ui.label("Journal");
+            let journal = &mut self.settings.journal_folder;
+            egui::ComboBox::from_id_salt("journal_folder")
+                .selected_text(journal.as_str())
+                .show_ui(ui, |ui| {
+                    for name in &names {
+                        if ui.selectable_label(journal == name, *name).clicked() && journal != name
+                        {
+                            *journal = name.to_string();
+                            msg = Some(Msg::SaveSettings);
+                        }
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Ajouter à");
+            let inbox = &mut self.settings.inbox_folder;
+            let selected = match inbox {
+                Some(folder) => format!("{folder}/Inbox.md"),
+                None => "la note du jour".to_owned(),
+            };
+            egui::ComboBox::from_id_salt("inbox_folder")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    if ui
+                        .selectable_label(inbox.is_none(), "la note du jour")
+                        .clicked()
+                        && inbox.is_some()
+                    {
+                        *inbox = None;
+                        msg = Some(Msg::SaveSettings);
+                    }
+                    for name in &names {
+                        let current = inbox.as_deref() == Some(*name);
+                        if ui
+                            .selectable_label(current, format!("{name}/Inbox.md"))
+                            .clicked()
+                            && !current
+                        {
+                            *inbox = Some(name.to_string());
+                            msg = Some(Msg::SaveSettings);
+                        }
+                    }
+                });
+        });
+
+        ui.separator();
+        ui.label(egui::RichText::new("Modèle des nouvelles notes (.notes/templates)").strong());
+        if self.templates.is_empty() {
+            ui.colored_label(Color32::GRAY, "Aucun modèle.");
+        } else {
+            msg = self.folder_templates(ui, &names).or(msg);
+        }
+
+        ui.separator();
+        ui.label(egui::RichText::new("Annulation").strong());
+        if ui
+            .checkbox(
+                &mut self.settings.persistent_undo,
+                "Garder les annulations entre les sessions",
+            )
+            .changed()
+        {
+            msg = Some(Msg::SaveSettings);
+        }
+
+        ui.separator();
+        ui.label(egui::RichText::new("Notes chiffrées").strong());
+        ui.horizontal(|ui| {
+            ui.label("Verrouiller après");
+            let delay = egui::DragValue::new(&mut self.settings.lock_after_minutes)
+                .range(0..=240)
+                .suffix(" min");
+            if ui.add(delay).changed() {
+                msg = Some(Msg::SaveSettings);
+            }
+            ui.colored_label(Color32::GRAY, "sans activité (0 : jamais)");
+        });
+
+        ui.separator();
+        ui.label(egui::RichText::new("Git").strong());
+        if let Some(m) = self.git(ui) {
+            msg = Some(m);
+        }
+
+        ui.separator();
+        ui.label(
+            egui::RichText::new("Version 0.1 – Demo")
+                .color(Color32::GRAY)
+                .small(),
+        );
+        msg
+    }
+
+    fn git(&mut self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        match self.git {
+            None => {
+                ui.colored_label(Color32::GRAY, "Le dossier de notes n’est pas un dépôt git.");
+                return None;
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::RED, e);
+            }
+            Some(Ok(status)) => {
+                ui.label(describe(status));
+            }
+        }
+        if ui
+            .checkbox(
+                &mut self.settings.git_auto_commit,
+                "Valider après une minute sans modification",
+            )
+            .changed()
+        {
+            msg = Some(Msg::SaveSettings);
+        }
+        if ui
+            .checkbox(
+                &mut self.settings.git_push,
+                "Pousser après chaque validation",
+            )
+            .changed()
+        {
+            msg = Some(Msg::SaveSettings);
+        }
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!self.git_busy, |ui| {
+                if ui.button("Valider maintenant").clicked() {
+                    msg = Some(Msg::GitCommit);
+                }
+                if ui.button("Pousser").clicked() {
+                    msg = Some(Msg::GitPush);
+                }
+            });
+            if ui.button("Actualiser").clicked() {
+                msg = Some(Msg::GitRefresh);
+            }
+            if self.git_busy {
+                ui.spinner();
+            }
+        });
+        msg
+    }
+
+    /// Default template of each folder.
+    fn folder_templates(&mut self, ui: &mut Ui, names: &[&str]) -> Option<Msg> {
+        let mut msg = None;
+        egui::Grid::new("folder_templates").show(ui, |ui| {
+            for name in names {
+                ui.label(*name);
+                let current = self.settings.folder_templates.get(*name).cloned();
+                egui::ComboBox::from_id_salt(("folder_template", *name))
+                    .selected_text(current.as_deref().unwrap_or("aucun"))
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(current.is_none(), "aucun").clicked()
+                            && current.is_some()
+                        {
+                            self.settings.folder_templates.remove(*name);
+                            msg = Some(Msg::SaveSettings);
+                        }
+                        for template in self.templates {
+                            let chosen = current.as_ref() == Some(template);
+                            if ui.selectable_label(chosen, template).clicked() && !chosen {
+                                self.settings
+                                    .folder_templates
+                                    .insert(name.to_string(), template.clone());
+                                msg = Some(Msg::SaveSettings);
+                            }
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+        msg
+    }
+}
+
+/// One-line summary of the repository status.
+fn describe(status: &Status) -> String {
+    let branch = status.branch.as_deref().unwrap_or("(détachée)");
+    let changed = match status.changed.len() {
+        0 => "aucune modification".to_owned(),
+        1 => "1 fichier modifié".to_owned(),
+        n => format!("{n} fichiers modifiés"),
+    };
+    let remote = match &status.upstream {
+        None => "aucune branche distante suivie".to_owned(),
+        Some(upstream) => format!(
+            "{} commit(s) à pousser et {} à récupérer sur {upstream}",
+            status.ahead, status.behind
+        ),
+    };
+    format!("Branche {branch} : {changed}, {remote}.")
+}
@@ -1,7 +1,9 @@
-use egui::{self, vec2, Color32, Image, ImageButton, Ui};
+use crate::app::{Msg, PaletteMode};
+use crate::ui::icons::Icons;
 use egui::epaint::StrokeKind;
-use crate::app::{Icons, Msg};
-use crate::models::Folder;
+use egui::{self, vec2, Color32, Image, ImageButton, Ui};
+use notes_app::journal;
+use notes_app::models::Folder;
 
 pub struct SideBar<'a> {
     folders: &'a [Folder],
@@ -31,8 +33,32 @@ impl<'a> SideBar<'a> {
     pub fn render(&self, ui: &mut Ui) -> Option<Msg> {
         ui.add_space(self.size * 0.3);
 
-        if icon(ui, &self.icons.search, self.size).clicked() {
-            // recherche future
+        if icon(ui, &self.icons.search, self.size)
+            .on_hover_text("Rechercher")
+            .clicked()
+        {
+            return Some(Msg::OpenPalette(PaletteMode::Search));
+        }
+        ui.add_space(self.size * 0.5);
+
+        // raccourcis du journal
+        if text_icon(ui, "📅", self.size)
+            .on_hover_text("Note du jour")
+            .clicked()
+        {
+            return Some(Msg::OpenDay(journal::today()));
+        }
+        if text_icon(ui, "⏴", self.size)
+            .on_hover_text("Jour précédent")
+            .clicked()
+        {
+            return Some(Msg::PreviousDay);
+        }
+        if text_icon(ui, "⏵", self.size)
+            .on_hover_text("Jour suivant")
+            .clicked()
+        {
+            return Some(Msg::NextDay);
         }
         ui.add_space(self.size * 0.5);
 
@@ -44,13 +70,22 @@ impl<'a> SideBar<'a> {
         }
         ui.add_space(self.size * 0.5);
 
-        if icon(ui, &self.icons.back, self.size).clicked() {
+        if icon(ui, &self.icons.back, self.size)
+            .on_hover_text("Retour")
+            .clicked()
+        {
             return Some(Msg::GoBack);
         }
+        if text_icon(ui, "➡", self.size)
+            .on_hover_text("Avancer")
+            .clicked()
+        {
+            return Some(Msg::GoForward);
+        }
         ui.add_space(self.size * 0.5);
 
         if icon(ui, &self.icons.add, self.size).clicked() {
-            return Some(Msg::CreateItem);       // bouton +
+            return Some(Msg::CreateItem); // bouton +
         }
         ui.add_space(self.size * 0.5);
 
@@ -60,7 +95,11 @@ impl<'a> SideBar<'a> {
         ui.add_space(self.size * 0.5);
 
         if icon(ui, &self.icons.settings, self.size)
-            .on_hover_text(if self.show_settings { "Paramètres (ouvert)" } else { "Paramètres" })
+            .on_hover_text(if self.show_settings {
+                "Paramètres (ouvert)"
+            } else {
+                "Paramètres"
+            })
             .clicked()
         {
             return Some(Msg::OpenSettings);
@@ -76,10 +115,16 @@ fn icon(ui: &mut Ui, tex: &egui::TextureHandle, size: f32) -> egui::Response {
     ui.add(ImageButton::new(image).frame(false))
 }
 
+fn text_icon(ui: &mut Ui, text: &str, size: f32) -> egui::Response {
+    let button = egui::Button::new(egui::RichText::new(text).size(size * 0.45)).frame(false);
+    ui.add_sized(vec2(size, size * 0.6), button)
+}
+
 fn folder_button(ui: &mut Ui, folder: &Folder, selected: bool, size: f32) -> egui::Response {
     let (rect, resp) = ui.allocate_exact_size(vec2(size, size), egui::Sense::click());
     let painter = ui.painter();
-    painter.rect_filled(rect, 4.0, folder.color);
+    let c = folder.color;
+    painter.rect_filled(rect, 4.0, Color32::from_rgb(c.r, c.g, c.b));
     if selected {
         painter.rect_stroke(
             rect,
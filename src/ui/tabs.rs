@@ -0,0 +1,87 @@
+use crate::app::Msg;
+use egui::{self, CursorIcon, RichText, Sense, Ui};
+
+/// Tabs of a pane; a tab is dragged to reorder it.
+pub struct TabBar<'a> {
+    titles: &'a [String],
+    active: usize,
+    pane: usize,
+    /// The pane edited: its active tab is highlighted.
+    focused: bool,
+    /// Shows the button splitting the editor, and whether it is split.
+    split: Option<bool>,
+}
+
+impl<'a> TabBar<'a> {
+    pub fn new(
+        titles: &'a [String],
+        active: usize,
+        pane: usize,
+        focused: bool,
+        split: Option<bool>,
+    ) -> Self {
+        Self {
+            titles,
+            active,
+            pane,
+            focused,
+            split,
+        }
+    }
+
+    pub fn render(&self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        ui.horizontal_wrapped(|ui| {
+            let mut rects = Vec::new();
+            let mut dropped = None;
+            for (idx, title) in self.titles.iter().enumerate() {
+                let mut text = RichText::new(title);
+                if self.focused && idx == self.active {
+                    text = text.strong();
+                }
+                let tab = ui
+                    .selectable_label(idx == self.active, text)
+                    .interact(Sense::drag());
+                if tab.clicked() {
+                    msg = Some(Msg::SelectTab(self.pane, idx));
+                }
+                if tab.dragged() {
+                    ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+                }
+                if tab.drag_stopped() {
+                    dropped = Some(idx);
+                }
+                rects.push(tab.rect);
+                if ui
+                    .small_button("×")
+                    .on_hover_text("Fermer (Ctrl+W)")
+                    .clicked()
+                {
+                    msg = Some(Msg::CloseTab(self.pane, idx));
+                }
+                ui.separator();
+            }
+            // l'onglet lâché prend la place de celui sous le pointeur
+            if let (Some(from), Some(pos)) = (dropped, ui.ctx().pointer_interact_pos()) {
+                let to = rects
+                    .iter()
+                    .position(|r| pos.x < r.right())
+                    .unwrap_or(rects.len().saturating_sub(1));
+                if to != from {
+                    msg = Some(Msg::MoveTab(self.pane, from, to));
+                }
+            }
+            if let Some(split) = self.split {
+                let label = if split { "Réunir" } else { "Diviser" };
+                if ui
+                    .button(label)
+                    .on_hover_text("Deux notes côte à côte")
+                    .clicked()
+                {
+                    msg = Some(Msg::ToggleSplit);
+                }
+            }
+        });
+        msg
+    }
+}
@@ -0,0 +1,64 @@
+use crate::app::Msg;
+use egui::{self, Color32, Key, TextEdit, Ui};
+
+/// Passphrase form of the encrypted notes.
+pub struct UnlockPanel<'a> {
+    passphrase: &'a mut String,
+    /// The passphrase typed again, when none was chosen for the vault yet.
+    confirmation: Option<&'a mut String>,
+    error: Option<&'a str>,
+}
+
+impl<'a> UnlockPanel<'a> {
+    pub fn new(
+        passphrase: &'a mut String,
+        confirmation: Option<&'a mut String>,
+        error: Option<&'a str>,
+    ) -> Self {
+        Self {
+            passphrase,
+            confirmation,
+            error,
+        }
+    }
+
+    pub fn render(self, ui: &mut Ui) -> Option<Msg> {
+        let mut msg = None;
+        if self.confirmation.is_some() {
+            ui.label("Choisissez la phrase de passe des notes chiffrées.");
+            ui.colored_label(
+                Color32::GRAY,
+                "Elle ne pourra pas être retrouvée si vous l’oubliez.",
+            );
+        } else {
+            ui.label("Phrase de passe des notes chiffrées");
+        }
+        let field = ui.add(TextEdit::singleline(self.passphrase).password(true));
+        let mut submitted = field.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+        if let Some(confirmation) = self.confirmation {
+            // l'Entrée du premier champ passe à la confirmation
+            if submitted {
+                ui.memory_mut(|m| m.request_focus(field.id.with("confirmation")));
+            }
+            ui.label("Retapez-la pour confirmer");
+            let field = ui.add(
+                TextEdit::singleline(confirmation)
+                    .password(true)
+                    .id(field.id.with("confirmation")),
+            );
+            submitted = field.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+        }
+        if submitted {
+            msg = Some(Msg::Unlock);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Déverrouiller").clicked() {
+                msg = Some(Msg::Unlock);
+            }
+        });
+        if let Some(error) = self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        msg
+    }
+}
@@ -0,0 +1,326 @@
+//! Undo history of the notes, kept by the app rather than by the editor
+//! widget so that it survives switching notes and reloads from disk. A step
+//! is the range of text it replaced, so long notes cost no more than what
+//! changed. The steps can be saved in `.history/undo/<note path>.json`.
+
+use crate::vault::Vault;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+/// Older steps are dropped beyond this.
+pub const MAX_STEPS: usize = 500;
+/// Typing merges into the previous step if it follows it within this delay.
+const MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+/// At byte `at`, `removed` was replaced by `inserted`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Edit {
+    pub at: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Edit {
+    /// The edit turning `old` into `new`, `None` when they are equal.
+    pub fn between(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let room = (old.len() - prefix).min(new.len() - prefix);
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(room)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix)
+        {
+            suffix -= 1;
+        }
+        Some(Self {
+            at: prefix,
+            removed: old[prefix..old.len() - suffix].to_owned(),
+            inserted: new[prefix..new.len() - suffix].to_owned(),
+        })
+    }
+
+    fn inverse(&self) -> Self {
+        Self {
+            at: self.at,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+
+    /// Applies the edit; `false`, leaving `text` as is, if it does not fit.
+    fn apply(&self, text: &mut String) -> bool {
+        let end = self.at + self.removed.len();
+        if text.get(self.at..end) != Some(self.removed.as_str()) {
+            return false;
+        }
+        text.replace_range(self.at..end, &self.inserted);
+        true
+    }
+
+    /// Extends this edit with `next`, typed right after it, when it carries
+    /// on the same word: more text, or backspaces over it or before it.
+    fn merge(&mut self, next: &Edit) -> bool {
+        if next.inserted.contains('\n') {
+            return false;
+        }
+        let end = self.at + self.inserted.len();
+        let next_end = next.at + next.removed.len();
+        if next.removed.is_empty() && next.at == end {
+            self.inserted.push_str(&next.inserted);
+        } else if next.inserted.is_empty() && next.at >= self.at && next_end == end {
+            self.inserted.truncate(next.at - self.at);
+        } else if next.inserted.is_empty() && self.inserted.is_empty() && next_end == self.at {
+            self.at = next.at;
+            self.removed.insert_str(0, &next.removed);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// Undo and redo steps of one note.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UndoStack {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    #[serde(skip)]
+    last_typed: Option<Instant>,
+    /// Changed since loaded or saved.
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl UndoStack {
+    /// Records the change from `old` to `new`. Typing (`typed`) merges with
+    /// the step typed just before; other changes, such as a reload from
+    /// disk, are steps of their own.
+    pub fn record(&mut self, old: &str, new: &str, typed: bool) {
+        let Some(edit) = Edit::between(old, new) else {
+            return;
+        };
+        self.redo.clear();
+        let recent = self.last_typed.is_some_and(|t| t.elapsed() < MERGE_WINDOW);
+        let merged = typed && recent && self.undo.last_mut().is_some_and(|last| last.merge(&edit));
+        if !merged {
+            self.undo.push(edit);
+            if self.undo.len() > MAX_STEPS {
+                self.undo.remove(0);
+            }
+        }
+        self.last_typed = typed.then(Instant::now);
+        self.dirty = true;
+    }
+
+    /// Undoes the last step on `text`. Returns where the cursor goes, as a
+    /// byte offset, or `None` if there was nothing to undo.
+    pub fn undo(&mut self, text: &mut String) -> Option<usize> {
+        let edit = self.undo.pop()?;
+        let cursor = self.step(&edit.inverse(), text)?;
+        self.redo.push(edit);
+        Some(cursor)
+    }
+
+    /// Redoes the last step undone, like `undo`.
+    pub fn redo(&mut self, text: &mut String) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        let cursor = self.step(&edit, text)?;
+        self.undo.push(edit);
+        Some(cursor)
+    }
+
+    fn step(&mut self, edit: &Edit, text: &mut String) -> Option<usize> {
+        self.last_typed = None;
+        self.dirty = true;
+        if !edit.apply(text) {
+            // le texte a changé sans passer par ici : les étapes ne valent plus
+            self.undo.clear();
+            self.redo.clear();
+            return None;
+        }
+        Some(edit.at + edit.inserted.len())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Erases the steps from memory, for an encrypted note being locked.
+    pub fn forget(&mut self) {
+        for edit in self.undo.iter_mut().chain(&mut self.redo) {
+            edit.removed.zeroize();
+            edit.inserted.zeroize();
+        }
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedStack<S> {
+    /// SHA-1 of the text the steps lead to: they only apply to that text.
+    head: String,
+    #[serde(flatten)]
+    stack: S,
+}
+
+/// Undo steps saved between sessions, in `.history/undo`.
+pub struct UndoLog {
+    dir: PathBuf,
+}
+
+impl UndoLog {
+    pub fn new(vault: &Vault) -> Self {
+        Self {
+            dir: vault.root().join(".history").join("undo"),
+        }
+    }
+
+    /// The steps saved for the note `rel`, empty if none were or if the
+    /// note changed since, as they would no longer apply to `text`.
+    pub fn load(&self, rel: &str, text: &str) -> io::Result<UndoStack> {
+        let json = match fs::read_to_string(self.path(rel)?) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(UndoStack::default()),
+            Err(e) => return Err(e),
+        };
+        let saved: SavedStack<UndoStack> =
+            serde_json::from_str(&json).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        Ok(if saved.head == hash(text) {
+            saved.stack
+        } else {
+            UndoStack::default()
+        })
+    }
+
+    /// Saves the steps of the note `rel`, which leave it at `text`.
+    pub fn save(&self, rel: &str, stack: &mut UndoStack, text: &str) -> io::Result<()> {
+        let path = self.path(rel)?;
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        let json = serde_json::to_string(&SavedStack {
+            head: hash(text),
+            stack: &*stack,
+        })?;
+        fs::write(&path, json)?;
+        stack.dirty = false;
+        Ok(())
+    }
+
+    pub fn remove(&self, rel: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(rel)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, rel: &str) -> io::Result<PathBuf> {
+        if rel.is_empty()
+            || !Path::new(rel)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid path: {rel}"),
+            ));
+        }
+        Ok(self.dir.join(format!("{rel}.json")))
+    }
+}
+
+fn hash(text: &str) -> String {
+    format!("{:x}", Sha1::digest(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types `new` over `text` as an edit of the stack.
+    fn type_to(stack: &mut UndoStack, text: &mut String, new: &str, typed: bool) {
+        stack.record(text, new, typed);
+        *text = new.to_owned();
+    }
+
+    #[test]
+    fn groups_typing_and_undoes_reloads_as_steps() {
+        let mut stack = UndoStack::default();
+        let mut text = String::from("# Notes\n");
+        type_to(&mut stack, &mut text, "# Notes\nb", true);
+        type_to(&mut stack, &mut text, "# Notes\nbo", true);
+        type_to(&mut stack, &mut text, "# Notes\nbon", true);
+        type_to(&mut stack, &mut text, "# Notes\nbo", true);
+        type_to(&mut stack, &mut text, "# Notes\nbon\n", true);
+        type_to(&mut stack, &mut text, "# Notes modifiées\nbon\n", false);
+
+        assert_eq!(stack.undo(&mut text), Some(7));
+        assert_eq!(text, "# Notes\nbon\n");
+        assert_eq!(stack.undo(&mut text), Some(10));
+        assert_eq!(text, "# Notes\nbo");
+        assert_eq!(stack.undo(&mut text), Some(8));
+        assert_eq!(text, "# Notes\n");
+        assert_eq!(stack.undo(&mut text), None);
+
+        assert_eq!(stack.redo(&mut text), Some(10));
+        assert_eq!(text, "# Notes\nbo");
+        type_to(&mut stack, &mut text, "# Notes\nbof", false);
+        assert_eq!(stack.redo(&mut text), None);
+
+        let accent = Edit::between("é!", "è!").unwrap();
+        assert_eq!(
+            (accent.at, accent.removed.as_str(), accent.inserted.as_str()),
+            (0, "é", "è")
+        );
+    }
+
+    #[test]
+    fn saved_steps_apply_only_to_the_same_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = UndoLog::new(&Vault::new(dir.path()));
+        let mut stack = UndoStack::default();
+        stack.record("un", "deux", false);
+        assert!(stack.is_dirty());
+        log.save("Travail/a.md", &mut stack, "deux").unwrap();
+        assert!(!stack.is_dirty());
+
+        assert_eq!(
+            log.load("Travail/a.md", "trois")
+                .unwrap()
+                .undo(&mut "trois".into()),
+            None
+        );
+        let mut text = String::from("deux");
+        assert_eq!(
+            log.load("Travail/a.md", &text).unwrap().undo(&mut text),
+            Some(2)
+        );
+        assert_eq!(text, "un");
+        assert!(log.load("../a.md", "").is_err());
+        log.remove("Travail/a.md").unwrap();
+        assert_eq!(
+            log.load("Travail/a.md", "deux")
+                .unwrap()
+                .undo(&mut "deux".into()),
+            None
+        );
+    }
+}
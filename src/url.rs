@@ -0,0 +1,73 @@
+//! Percent-encoding of the paths used in URLs, shared by the HTML export and
+//! the web servers.
+
+/// `s` as one URL path segment: every byte but the unreserved ones is
+/// percent-encoded, so `#`, `?`, `"` or `/` in a note name stay in the name.
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// A relative `/`-separated path as a URL path, each segment encoded.
+pub fn percent_encode_path(path: &str) -> String {
+    path.split('/')
+        .map(percent_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Decodes the `%XX` escapes of `s`; a `%` not followed by two hex digits
+/// stays as it is.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_paths() {
+        assert_eq!(
+            percent_decode("Mes%20notes/%C3%A9t%C3%A9.html"),
+            "Mes notes/été.html"
+        );
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let name = "Q&R #1?/été \"100%\".md";
+        assert_eq!(
+            percent_encode(name),
+            "Q%26R%20%231%3F%2F%C3%A9t%C3%A9%20%22100%25%22.md"
+        );
+        assert_eq!(percent_decode(&percent_encode(name)), name);
+        assert_eq!(
+            percent_encode_path("Mes notes/a#b.html"),
+            "Mes%20notes/a%23b.html"
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use crate::crypt;
+use crate::models::{Color, Folder, Note};
+use crate::render;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+/// Colour given to folders, which have no stored colour on disk.
+pub const DEFAULT_FOLDER_COLOR: Color = Color::rgb(100, 100, 200);
+
+/// A directory of notes: every sub-directory is a [`Folder`] and every
+/// `.md` file inside it a [`Note`]. Hidden entries (`.history`, …) are skipped.
+#[derive(Clone)]
+pub struct Vault {
+    root: PathBuf,
+}
+
+/// Notes that could not be read while loading (not UTF-8, not readable…),
+/// with the reason.
+pub type Skipped = Vec<(PathBuf, io::Error)>;
+
+/// A note matching a [`Vault::search`] query.
+pub struct SearchHit {
+    pub note: Note,
+    /// A few words around the first match in the body.
+    pub snippet: String,
+}
+
+impl Vault {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Hidden directory holding the vault settings (never listed as a folder
+    /// nor reachable through [`Vault::resolve`]).
+    pub fn config_dir(&self) -> PathBuf {
+        self.root.join(".notes")
+    }
+
+    /// Loads every folder and its notes, sorted by name. A note that cannot
+    /// be read is left out and returned apart, the rest still loads.
+    pub fn load(&self) -> io::Result<(Vec<Folder>, Skipped)> {
+        let (mut folders, mut skipped) = (Vec::new(), Vec::new());
+        for path in sorted_entries(&self.root)? {
+            if path.is_dir() && !is_hidden(&path) {
+                let (folder, unread) = load_folder(&path)?;
+                folders.push(folder);
+                skipped.extend(unread);
+            }
+        }
+        Ok((folders, skipped))
+    }
+
+    /// [`Vault::load`], without the notes that could not be read.
+    pub fn folders(&self) -> io::Result<Vec<Folder>> {
+        Ok(self.load()?.0)
+    }
+
+    /// Maps a vault-relative path (`Dossier/note.md`) to a path on disk,
+    /// refusing anything that could escape the vault: absolute paths, `..`,
+    /// hidden components and symlinks pointing outside.
+    pub fn resolve(&self, rel: &str) -> io::Result<PathBuf> {
+        let rel_path = Path::new(rel);
+        if rel.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "empty path"));
+        }
+        for component in rel_path.components() {
+            match component {
+                Component::Normal(name) if !name.to_string_lossy().starts_with('.') => {}
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("invalid path: {rel}"),
+                    ))
+                }
+            }
+        }
+
+        let path = self.root.join(rel_path);
+        let mut existing = path.as_path();
+        while !existing.exists() {
+            existing = match existing.parent() {
+                Some(parent) if parent.starts_with(&self.root) => parent,
+                _ => return Err(io::Error::new(ErrorKind::NotFound, "vault not found")),
+            };
+        }
+        if !fs::canonicalize(existing)?.starts_with(fs::canonicalize(&self.root)?) {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("outside of the vault: {rel}"),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Vault-relative form of `path`, with `/` separators.
+    pub fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn read_note(&self, rel: &str) -> io::Result<Note> {
+        load_note(&self.note_path(rel)?)
+    }
+
+    /// Replaces the body of a note, creating it if its folder exists.
+    pub fn write_note(&self, rel: &str, body: &str) -> io::Result<Note> {
+        let path = self.note_path(rel)?;
+        if !path.parent().is_some_and(Path::is_dir) {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no folder for {rel}"),
+            ));
+        }
+        fs::write(&path, body)?;
+        load_note(&path)
+    }
+
+    /// Creates `folder/<title>.md`; fails if a note with that name exists.
+    pub fn create_note(&self, folder: &str, title: &str, body: &str) -> io::Result<Note> {
+        let name = file_name(title);
+        if name.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "empty title"));
+        }
+        let dir = self.resolve(folder)?;
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no folder {folder}"),
+            ));
+        }
+        let path = dir.join(format!("{name}.md"));
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        fs::write(&path, body)?;
+        load_note(&path)
+    }
+
+    /// Creates an empty folder at the root of the vault.
+    pub fn create_folder(&self, name: &str) -> io::Result<Folder> {
+        let name = file_name(name);
+        if name.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "empty folder name"));
+        }
+        let path = self.root.join(&name);
+        fs::create_dir(&path)?;
+        Ok(Folder::new(&name, DEFAULT_FOLDER_COLOR, path))
+    }
+
+    pub fn delete_note(&self, rel: &str) -> io::Result<()> {
+        fs::remove_file(self.note_path(rel)?)
+    }
+
+    /// Moves a note into the folder `to`, or renames it when `to` ends with
+    /// `.md`. Never overwrites an existing note.
+    pub fn move_note(&self, rel: &str, to: &str) -> io::Result<Note> {
+        let from = self.note_path(rel)?;
+        if !from.is_file() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no note {rel}"),
+            ));
+        }
+        let dest = if to.ends_with(".md") {
+            self.note_path(to)?
+        } else {
+            self.resolve(to)?.join(from.file_name().unwrap_or_default())
+        };
+        if !dest.parent().is_some_and(Path::is_dir) {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no folder for {to}"),
+            ));
+        }
+        if dest.exists() {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", self.relative(&dest)),
+            ));
+        }
+        fs::rename(&from, &dest)?;
+        load_note(&dest)
+    }
+
+    /// Notes by `#tag` (lowercased).
+    pub fn tags(&self) -> io::Result<BTreeMap<String, Vec<Note>>> {
+        let mut tagged: BTreeMap<String, Vec<Note>> = BTreeMap::new();
+        for folder in self.folders()? {
+            for note in folder
+                .notes
+                .into_iter()
+                .filter(|n| !crypt::is_encrypted(&n.body))
+            {
+                for tag in render::tags(&note.body) {
+                    tagged.entry(tag).or_default().push(note.clone());
+                }
+            }
+        }
+        Ok(tagged)
+    }
+
+    /// Case-insensitive search in note titles and bodies.
+    pub fn search(&self, query: &str) -> io::Result<Vec<SearchHit>> {
+        let query = query.trim().to_lowercase();
+        let mut hits = Vec::new();
+        if query.is_empty() {
+            return Ok(hits);
+        }
+        for folder in self.folders()? {
+            // les notes chiffrées ne sont pas cherchées, même par titre
+            for note in folder
+                .notes
+                .into_iter()
+                .filter(|n| !crypt::is_encrypted(&n.body))
+            {
+                let body = note.body.to_lowercase();
+                let in_title = note.title.to_lowercase().contains(&query);
+                let at = body.find(&query);
+                if in_title || at.is_some() {
+                    let snippet = snippet(&note.body, &body, at.unwrap_or(0), query.len());
+                    hits.push(SearchHit { note, snippet });
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    fn note_path(&self, rel: &str) -> io::Result<PathBuf> {
+        if !rel.ends_with(".md") {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("not a Markdown note: {rel}"),
+            ));
+        }
+        self.resolve(rel)
+    }
+}
+
+/// Loads the notes of the folder at `path`, and the ones it could not read.
+pub fn load_folder(path: &Path) -> io::Result<(Folder, Skipped)> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut folder = Folder::new(&name, DEFAULT_FOLDER_COLOR, path.to_path_buf());
+    let mut skipped = Vec::new();
+    for file in sorted_entries(path)? {
+        if file.is_file() && !is_hidden(&file) && file.extension().is_some_and(|e| e == "md") {
+            match load_note(&file) {
+                Ok(note) => folder.notes.push(note),
+                Err(e) => skipped.push((file, e)),
+            }
+        }
+    }
+    Ok((folder, skipped))
+}
+
+/// Reads a note; its title is the file name without extension.
+pub fn load_note(path: &Path) -> io::Result<Note> {
+    Ok(Note {
+        title: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        body: fs::read_to_string(path)?,
+        path: path.to_path_buf(),
+    })
+}
+
+fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+}
+
+/// Turns a title into a file name, dropping path separators and other
+/// characters that are not allowed on common file systems.
+pub(crate) fn file_name(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| {
+            !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') && !c.is_control()
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_owned()
+}
+
+fn snippet(body: &str, lower: &str, at: usize, len: usize) -> String {
+    // `lower` peut différer de `body` en longueur : on retombe sur le début
+    if lower.len() != body.len() || !body.is_char_boundary(at) || !body.is_char_boundary(at + len) {
+        return body.chars().take(80).collect();
+    }
+    let start = body[..at]
+        .char_indices()
+        .rev()
+        .nth(30)
+        .map_or(0, |(i, _)| i);
+    let end = body[at + len..]
+        .char_indices()
+        .nth(50)
+        .map_or(body.len(), |(i, _)| at + len + i);
+    body[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> (tempfile::TempDir, Vault) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Travail")).unwrap();
+        fs::create_dir(dir.path().join(".history")).unwrap();
+        fs::write(
+            dir.path().join("Travail/réunion.md"),
+            "# Lundi\nBudget validé",
+        )
+        .unwrap();
+        fs::write(dir.path().join("Travail/notes.txt"), "pas une note").unwrap();
+        let vault = Vault::new(dir.path());
+        (dir, vault)
+    }
+
+    #[test]
+    fn loads_folders_and_notes() {
+        let (_dir, vault) = vault();
+        let folders = vault.folders().unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].name, "Travail");
+        assert_eq!(folders[0].notes.len(), 1);
+        assert_eq!(folders[0].notes[0].title, "réunion");
+    }
+
+    #[test]
+    fn skips_notes_that_are_not_utf8() {
+        let (dir, vault) = vault();
+        let bad = dir.path().join("Travail/latin1.md");
+        fs::write(&bad, b"caf\xe9").unwrap();
+        let (folders, skipped) = vault.load().unwrap();
+        assert_eq!(folders[0].notes.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, bad);
+        assert_eq!(skipped[0].1.kind(), ErrorKind::InvalidData);
+        assert_eq!(vault.folders().unwrap()[0].notes.len(), 1);
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_vault() {
+        let (_dir, vault) = vault();
+        for rel in [
+            "../etc/passwd",
+            "/etc/passwd",
+            "Travail/../../x.md",
+            ".history/x.md",
+            "",
+        ] {
+            assert!(vault.resolve(rel).is_err(), "{rel}");
+        }
+        assert!(vault.read_note("Travail/réunion.md").is_ok());
+        assert!(vault.read_note("Travail/notes.txt").is_err());
+    }
+
+    #[test]
+    fn creates_searches_and_deletes_notes() {
+        let (_dir, vault) = vault();
+        let note = vault
+            .create_note("Travail", "Idées/../projet", "Un budget")
+            .unwrap();
+        assert_eq!(vault.relative(&note.path), "Travail/Idées..projet.md");
+        assert!(vault.create_note("Travail", "Idées/../projet", "").is_err());
+
+        let hits = vault.search("BUDGET").unwrap();
+        assert_eq!(hits.len(), 2);
+
+        vault.delete_note("Travail/Idées..projet.md").unwrap();
+        assert_eq!(vault.search("budget").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn moves_notes_and_lists_tags() {
+        let (_dir, vault) = vault();
+        vault.create_folder("Archives").unwrap();
+        vault
+            .write_note("Travail/budget.md", "#Finances et #projet")
+            .unwrap();
+        let moved = vault.move_note("Travail/budget.md", "Archives").unwrap();
+        assert_eq!(vault.relative(&moved.path), "Archives/budget.md");
+        let renamed = vault
+            .move_note("Archives/budget.md", "Archives/budget 2024.md")
+            .unwrap();
+        assert_eq!(renamed.title, "budget 2024");
+        assert!(vault
+            .move_note("Travail/réunion.md", "Archives/budget 2024.md")
+            .is_err());
+        assert!(vault.move_note("Travail/réunion.md", "Nulle part").is_err());
+
+        let tags = vault.tags().unwrap();
+        assert_eq!(tags.keys().collect::<Vec<_>>(), ["finances", "projet"]);
+        assert_eq!(tags["projet"][0].title, "budget 2024");
+    }
+
+    #[test]
+    fn creates_folders() {
+        let (_dir, vault) = vault();
+        let folder = vault.create_folder("Perso/../Voyages").unwrap();
+        assert_eq!(folder.name, "Perso..Voyages");
+        assert!(vault.create_folder("Perso/../Voyages").is_err());
+        assert!(vault.create_folder("../").is_err());
+        vault.create_note("Perso..Voyages", "Lisbonne", "").unwrap();
+        assert_eq!(vault.folders().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! File system watching for the front ends: the app reloads notes edited
+//! elsewhere, `md_watch` rebuilds the pages of changed files.
+
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<notify::Event>>,
+}
+
+impl FileWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (tx, rx) = channel();
+        let watcher = RecommendedWatcher::new(tx, Config::default())?;
+        Ok(Self { watcher, rx })
+    }
+
+    /// Watches a file, or a directory with everything below it when `recursive`.
+    pub fn watch(&mut self, path: &Path, recursive: bool) -> notify::Result<()> {
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        self.watcher.watch(path, mode)
+    }
+
+    /// Paths touched since the last call, without waiting.
+    pub fn changes(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        for res in self.rx.try_iter() {
+            touched(res, &mut paths);
+        }
+        dedup(paths)
+    }
+
+    /// Waits for the next burst of file system events and returns the touched
+    /// paths once no event arrived for `quiet` (editors emit several `Modify`
+    /// events per save). Returns `None` when the watcher is gone.
+    pub fn next_batch(&self, quiet: Duration) -> Option<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        touched(self.rx.recv().ok()?, &mut paths);
+        loop {
+            match self.rx.recv_timeout(quiet) {
+                Ok(res) => touched(res, &mut paths),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+        Some(dedup(paths))
+    }
+}
+
+fn touched(res: notify::Result<notify::Event>, paths: &mut Vec<PathBuf>) {
+    match res {
+        Ok(event) if !matches!(event.kind, EventKind::Access(_)) => paths.extend(event.paths),
+        Ok(_) => {}
+        Err(e) => eprintln!("watch error: {e}"),
+    }
+}
+
+fn dedup(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reports_changed_files_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("Travail")).unwrap();
+        let mut watcher = FileWatcher::new().unwrap();
+        watcher.watch(&root, true).unwrap();
+
+        let note = root.join("Travail/idée.md");
+        fs::write(&note, "un").unwrap();
+        fs::write(&note, "deux").unwrap();
+        let batch = watcher.next_batch(Duration::from_millis(200)).unwrap();
+        assert!(batch.contains(&note), "{batch:?}");
+        assert!(watcher.changes().is_empty());
+    }
+}